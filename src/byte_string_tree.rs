@@ -0,0 +1,208 @@
+use crate::*;
+use std::fmt;
+
+
+
+/// A tree that maps arbitrary byte strings (which don't have to be valid UTF-8) to values
+/// 
+/// This uses the exact same storage as StringTree, so converting between the two doesn't copy any nodes
+pub struct ByteStringTree<T> {
+	pub(crate) tree: StringTree<T>,
+}
+
+impl<T> ByteStringTree<T> {
+	
+	/// Creates a new, empty ByteStringTree
+	pub fn new() -> Self {
+		Self {
+			tree: StringTree::new(),
+		}
+	}
+	/// Creates a new ByteStringTree with a given list of key/value pairs
+	pub fn from<S: AsRef<[u8]>, I: IntoIterator<Item = (S, T)>>(source: I) -> Self {
+		let mut output = Self::new();
+		for (key, value) in source {
+			output.insert(key, value);
+		}
+		output
+	}
+	
+	/// Inserts a key/value pair into the tree, and returns the previous value if it exists
+	pub fn insert(&mut self, key: impl AsRef<[u8]>, value: T) -> Option<T> {
+		let output = self.tree.set_at(0, key.as_ref(), value);
+		self.tree.check_invariants_bytes();
+		output
+	}
+	/// Gets a value from a given key (or None)
+	pub fn get(&self, key: impl AsRef<[u8]>) -> Option<&T> {
+		let index = self.tree.descend(0, key.as_ref())?;
+		self.tree.node_values.get(index as usize)
+	}
+	/// Gets a value from a given key (or None)
+	pub fn get_mut(&mut self, key: impl AsRef<[u8]>) -> Option<&mut T> {
+		let index = self.tree.descend(0, key.as_ref())?;
+		self.tree.touch(index);
+		self.tree.check_invariants_bytes();
+		self.tree.node_values.get_mut(index as usize)
+	}
+	/// Returns true if the key has a value
+	pub fn contains_key(&self, key: impl AsRef<[u8]>) -> bool {
+		self.get(key).is_some()
+	}
+	/// Removes and returns a value from a given key (or None if there was no value at the given key)
+	pub fn remove(&mut self, key: impl AsRef<[u8]>) -> Option<T> {
+		let output = self.tree.remove_at(0, key.as_ref());
+		self.tree.check_invariants_bytes();
+		output
+	}
+	
+	/// Iterates over every key/value pair in the tree, sorted by key bytes
+	pub fn iter(&self) -> impl Iterator<Item = (Vec<u8>, &T)> {
+		self.tree.iter_sequences_from(Some(0), vec!())
+	}
+	/// Iterates over every key/value pair whose key starts with `prefix`, sorted by key bytes
+	pub fn iter_prefix(&self, prefix: impl AsRef<[u8]>) -> impl Iterator<Item = (Vec<u8>, &T)> {
+		let prefix = prefix.as_ref();
+		self.tree.iter_sequences_from(self.tree.descend(0, prefix), prefix.to_vec())
+	}
+	
+	/// Steps further into the tree and returns a node reference (or None)
+	pub fn step<'a>(&'a self, key: impl AsRef<[u8]>) -> Option<ByteStringTreeNode<'a, T>> {
+		self.root_node().step(key)
+	}
+	pub const fn root_node<'a>(&'a self) -> ByteStringTreeNode<'a, T> {
+		ByteStringTreeNode {
+			ref_tree: &self.tree,
+			index: 0,
+		}
+	}
+	
+	/// Checks every internal invariant of the tree, see `StringTree::validate()`
+	/// 
+	/// Unlike StringTree, paths that aren't valid UTF-8 are allowed here
+	pub fn validate(&self) -> Result<(), Vec<IntegrityError>> {
+		let errors = self.tree.integrity_errors(|_| true);
+		if errors.is_empty() {Ok(())} else {Err(errors)}
+	}
+	
+	/// Converts this into a StringTree, or returns an error (which holds this tree) if any key isn't valid UTF-8
+	pub fn into_string_tree(self) -> Result<StringTree<T>, NonUtf8KeyError<T>> {
+		let invalid_key = self.iter().map(|(key, _)| key).find(|key| str::from_utf8(key).is_err());
+		match invalid_key {
+			Some(key) => Err(NonUtf8KeyError {tree: Box::new(self), key}),
+			None => Ok(self.tree),
+		}
+	}
+	
+}
+
+impl<T> From<StringTree<T>> for ByteStringTree<T> {
+	fn from(tree: StringTree<T>) -> Self {
+		Self {
+			tree,
+		}
+	}
+}
+
+impl<T> TryFrom<ByteStringTree<T>> for StringTree<T> {
+	type Error = NonUtf8KeyError<T>;
+	fn try_from(tree: ByteStringTree<T>) -> Result<Self, Self::Error> {
+		tree.into_string_tree()
+	}
+}
+
+impl<T> Default for ByteStringTree<T> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<T: Clone> Clone for ByteStringTree<T> {
+	fn clone(&self) -> Self {
+		Self {
+			tree: self.tree.clone(),
+		}
+	}
+}
+
+
+
+/// The error from converting a ByteStringTree into a StringTree when one of its keys isn't valid UTF-8
+pub struct NonUtf8KeyError<T> {
+	tree: Box<ByteStringTree<T>>,
+	key: Vec<u8>,
+}
+
+impl<T> NonUtf8KeyError<T> {
+	/// Returns the first key (in sorted order) that isn't valid UTF-8
+	pub fn key(&self) -> &[u8] {
+		&self.key
+	}
+	/// Returns the tree that couldn't be converted
+	pub fn into_tree(self) -> ByteStringTree<T> {
+		*self.tree
+	}
+}
+
+impl<T> fmt::Debug for NonUtf8KeyError<T> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("NonUtf8KeyError").field("key", &self.key).finish_non_exhaustive()
+	}
+}
+
+impl<T> fmt::Display for NonUtf8KeyError<T> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "key {:?} is not valid UTF-8", self.key)
+	}
+}
+
+impl<T> std::error::Error for NonUtf8KeyError<T> {}
+
+
+
+/// A reference to a node within a ByteStringTree
+pub struct ByteStringTreeNode<'a, T> {
+	pub(crate) ref_tree: &'a StringTree<T>,
+	pub(crate) index: u32,
+}
+
+impl<'a, T> ByteStringTreeNode<'a, T> {
+	
+	/// Steps further into the tree and returns the value at the desired position (or None)
+	pub fn get(&self, key: impl AsRef<[u8]>) -> Option<&'a T> {
+		let index = self.ref_tree.descend(self.index, key.as_ref())?;
+		self.ref_tree.node_values.get(index as usize)
+	}
+	/// Steps further into the tree and returns a new node reference (or None)
+	pub fn step(&self, key: impl AsRef<[u8]>) -> Option<ByteStringTreeNode<'a, T>> {
+		let index = self.ref_tree.descend(self.index, key.as_ref())?;
+		Some(Self {
+			ref_tree: self.ref_tree,
+			index,
+		})
+	}
+	
+	/// Returns the value at this node (or None)
+	pub fn value(&self) -> Option<&'a T> {
+		self.ref_tree.node_values.get(self.index as usize)
+	}
+	
+	/// Returns a handle to this node that stays valid until this node is removed
+	pub fn id(&self) -> NodeId {
+		self.ref_tree.id_of(self.index)
+	}
+	
+	/// Creates and returns the bytes that are needed to reach this node from the root node
+	pub fn path_bytes(&self) -> Vec<u8> {
+		self.ref_tree.path_of(self.index)
+	}
+	
+	/// Iterates over the children of this node, sorted by byte
+	/// 
+	/// Unlike `StringTreeNode::children()`, this always goes exactly one byte deeper, even in the middle of a multi-byte char
+	pub fn children_bytes(&self) -> impl Iterator<Item = (u8, ByteStringTreeNode<'a, T>)> + use<'a, T> {
+		let ref_tree = self.ref_tree;
+		self.ref_tree.sorted_children(self.index).into_iter().map(move |(byte, index)| (byte, ByteStringTreeNode {ref_tree, index}))
+	}
+	
+}
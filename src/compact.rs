@@ -0,0 +1,85 @@
+use crate::*;
+use std::{collections::VecDeque, mem};
+
+
+
+/// The order that `StringTree::compact()` puts the nodes in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CompactOrder {
+	/// Every node is followed by its whole subtree, which is best for iterating over keys and prefixes
+	#[default]
+	DepthFirst,
+	/// Nodes are sorted by depth, which is best for lookups that only go a few nodes deep
+	BreadthFirst,
+}
+
+impl<T> StringTree<T> {
+	/// Renumbers every node so that they are stored in the given order (with children sorted by byte), and returns how many nodes changed position
+	/// 
+	/// After many removals, nodes that are close in the tree can end up far apart in memory, and this puts them back together. This also drops any nodes that can't be reached from the root node.
+	/// 
+	/// Note: this invalidates the NodeId of every node that changed position, and any StringTreeNode or StringTreeNodeMut that was created before this should not be used
+	pub fn compact(&mut self, order: CompactOrder) -> usize {
+		// find the new order of the nodes
+		let mut new_to_old = Vec::with_capacity(self.node_pointers.len());
+		match order {
+			CompactOrder::DepthFirst => {
+				let mut stack = vec!(0);
+				while let Some(index) = stack.pop() {
+					new_to_old.push(index);
+					stack.extend(self.sorted_children(index).into_iter().rev().map(|(_, child_index)| child_index));
+				}
+			}
+			CompactOrder::BreadthFirst => {
+				let mut queue = VecDeque::from([0]);
+				while let Some(index) = queue.pop_front() {
+					new_to_old.push(index);
+					queue.extend(self.sorted_children(index).into_iter().map(|(_, child_index)| child_index));
+				}
+			}
+		}
+		let mut old_to_new = vec!(u32::MAX; self.node_pointers.len());
+		for (new_index, &old_index) in new_to_old.iter().enumerate() {
+			old_to_new[old_index as usize] = new_index as u32;
+		}
+		let moved_count = new_to_old.iter().enumerate().filter(|(new_index, old_index)| *new_index as u32 != **old_index).count();
+		
+		// move everything to its new position
+		let mut node_pointers = Vec::with_capacity(new_to_old.len());
+		let mut node_fill_counts = Vec::with_capacity(new_to_old.len());
+		let mut node_stubs = Vec::with_capacity(new_to_old.len());
+		let mut node_parents = Vec::with_capacity(new_to_old.len());
+		let mut node_values = NodeValues::with_capacity(new_to_old.len());
+		let mut node_versions = Vec::with_capacity(new_to_old.len());
+		for &old_index in &new_to_old {
+			let old_index = old_index as usize;
+			let mut pointers = mem::take(&mut self.node_pointers[old_index]);
+			pointers.sort_unstable_by_key(|(byte, _)| *byte);
+			for (_, child_index) in &mut pointers {
+				*child_index = old_to_new[*child_index as usize];
+			}
+			node_pointers.push(pointers);
+			node_fill_counts.push(self.node_fill_counts[old_index]);
+			node_stubs.push(self.node_stubs[old_index]);
+			let (parent_index, index_within_parent) = self.node_parents[old_index];
+			node_parents.push((if old_index == 0 {0} else {old_to_new[parent_index as usize]}, index_within_parent));
+			node_values.push(self.node_values.take(old_index));
+			node_versions.push(self.node_versions[old_index]);
+		}
+		self.node_pointers = node_pointers;
+		self.node_fill_counts = node_fill_counts;
+		self.node_stubs = node_stubs;
+		self.node_parents = node_parents;
+		self.node_values = node_values;
+		self.node_versions = node_versions;
+		self.free_nodes.clear();
+		for (index, generation) in self.node_generations.iter_mut().enumerate() {
+			if new_to_old.get(index) != Some(&(index as u32)) {
+				*generation = generation.wrapping_add(1);
+			}
+		}
+		self.check_invariants();
+		
+		moved_count
+	}
+}
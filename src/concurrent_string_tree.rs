@@ -0,0 +1,158 @@
+use crate::*;
+use std::{cmp::Reverse, collections::BinaryHeap, sync::{RwLock, RwLockReadGuard, RwLockWriteGuard, atomic::{AtomicUsize, Ordering}}};
+
+
+
+const SHARD_COUNT: usize = 256;
+const SHARD_PREFIX_LEN: usize = 8;
+
+/// A thread-safe StringTree that can be read and edited from many threads at once through `&self`
+/// 
+/// Keys are split into 256 subtrees based on a hash of their first 8 bytes, and each subtree has its own lock, so operations on keys that land in different subtrees never block each other. Since only the first few bytes are hashed, every key that starts with the same 8 bytes is in the same subtree, which lets long prefix scans stay within one subtree.
+/// 
+/// If a thread panics while it holds a subtree's lock, the subtree might be left half-edited, so every later operation that needs that subtree panics too (this is the lock's poisoning)
+pub struct ConcurrentStringTree<T> {
+	pub(crate) shards: Box<[RwLock<StringTree<T>>]>,
+	pub(crate) len: AtomicUsize,
+}
+
+impl<T> ConcurrentStringTree<T> {
+	
+	/// Creates a new, empty ConcurrentStringTree
+	pub fn new() -> Self {
+		Self {
+			shards: (0..SHARD_COUNT).map(|_| RwLock::new(StringTree::new())).collect(),
+			len: AtomicUsize::new(0),
+		}
+	}
+	/// Creates a new ConcurrentStringTree with a given list of key/value pairs
+	pub fn from<S: AsRef<str>, I: IntoIterator<Item = (S, T)>>(source: I) -> Self {
+		let output = Self::new();
+		for (key, value) in source {
+			output.insert(key, value);
+		}
+		output
+	}
+	
+	/// Returns the subtree for a key, which is an FNV-1a hash of the key's first `SHARD_PREFIX_LEN` bytes
+	pub(crate) fn shard_index(key: &str) -> usize {
+		let mut hash = 0xcbf29ce484222325u64;
+		for &byte in key.as_bytes().iter().take(SHARD_PREFIX_LEN) {
+			hash ^= byte as u64;
+			hash = hash.wrapping_mul(0x100000001b3);
+		}
+		(hash ^ hash >> 32) as usize % SHARD_COUNT
+	}
+	fn read_shard(&self, index: usize) -> RwLockReadGuard<'_, StringTree<T>> {
+		self.shards[index].read().unwrap()
+	}
+	fn write_shard(&self, index: usize) -> RwLockWriteGuard<'_, StringTree<T>> {
+		self.shards[index].write().unwrap()
+	}
+	
+	/// Inserts a key/value pair into the tree, and returns the previous value if it exists
+	pub fn insert(&self, key: impl AsRef<str>, value: T) -> Option<T> {
+		let key = key.as_ref();
+		let output = self.write_shard(Self::shard_index(key)).insert(key, value);
+		if output.is_none() {
+			self.len.fetch_add(1, Ordering::Relaxed);
+		}
+		output
+	}
+	/// Removes and returns a value from a given key (or None if there was no value at the given key)
+	pub fn remove(&self, key: impl AsRef<str>) -> Option<T> {
+		let key = key.as_ref();
+		let output = self.write_shard(Self::shard_index(key)).remove(key);
+		if output.is_some() {
+			self.len.fetch_sub(1, Ordering::Relaxed);
+		}
+		output
+	}
+	/// Gets a clone of the value at a given key (or None)
+	pub fn get(&self, key: impl AsRef<str>) -> Option<T> where T: Clone {
+		self.get_with(key, T::clone)
+	}
+	/// Calls `f` with the value at a given key and returns its result (or None if there is no value)
+	/// 
+	/// The key's subtree stays read-locked while `f` runs
+	pub fn get_with<R>(&self, key: impl AsRef<str>, f: impl FnOnce(&T) -> R) -> Option<R> {
+		let key = key.as_ref();
+		self.read_shard(Self::shard_index(key)).get(key).map(f)
+	}
+	/// Calls `f` with the value at a given key as mut and returns its result (or None if there is no value)
+	/// 
+	/// The key's subtree stays write-locked while `f` runs
+	pub fn get_mut_with<R>(&self, key: impl AsRef<str>, f: impl FnOnce(&mut T) -> R) -> Option<R> {
+		let key = key.as_ref();
+		self.write_shard(Self::shard_index(key)).get_mut(key).map(f)
+	}
+	/// Returns true if the key is in the tree
+	pub fn contains(&self, key: impl AsRef<str>) -> bool {
+		self.get_with(key, |_| ()).is_some()
+	}
+	
+	/// Calls `f` with every key/value pair whose key starts with `prefix`, sorted by key bytes
+	/// 
+	/// If `prefix` is at least 8 bytes long, only its subtree is read-locked while its pairs are visited. Otherwise, the matching keys can be in any subtree, so every subtree is read-locked (always in the same order) until this returns, which gives `f` a consistent view of the whole tree but makes edits from other threads wait until then. Either way, `f` must not use this tree (editing it would deadlock, and so can reading it while another thread is waiting to edit it).
+	pub fn for_each_prefix(&self, prefix: impl AsRef<str>, mut f: impl FnMut(&str, &T)) {
+		let prefix = prefix.as_ref();
+		if prefix.len() >= SHARD_PREFIX_LEN {
+			let shard = self.read_shard(Self::shard_index(prefix));
+			for (key, value) in shard.iter_prefix(prefix) {
+				f(&key, value);
+			}
+			return;
+		}
+		// merge the sorted pairs of every subtree as they are iterated
+		let shards = (0..SHARD_COUNT).map(|shard_index| self.read_shard(shard_index)).collect::<Vec<_>>();
+		let mut shard_iters = shards.iter().map(|shard| shard.iter_prefix(prefix)).collect::<Vec<_>>();
+		let mut next_values = vec!(None; SHARD_COUNT);
+		let mut next_keys = BinaryHeap::new();
+		for (shard_index, shard_iter) in shard_iters.iter_mut().enumerate() {
+			if let Some((key, value)) = shard_iter.next() {
+				next_values[shard_index] = Some(value);
+				next_keys.push(Reverse((key, shard_index)));
+			}
+		}
+		while let Some(Reverse((key, shard_index))) = next_keys.pop() {
+			f(&key, next_values[shard_index].take().unwrap());
+			if let Some((key, value)) = shard_iters[shard_index].next() {
+				next_values[shard_index] = Some(value);
+				next_keys.push(Reverse((key, shard_index)));
+			}
+		}
+	}
+	/// Returns clones of every key/value pair whose key starts with `prefix`, sorted by key bytes
+	pub fn collect_prefix(&self, prefix: impl AsRef<str>) -> Vec<(String, T)> where T: Clone {
+		let mut output = vec!();
+		self.for_each_prefix(prefix, |key, value| output.push((key.to_string(), value.clone())));
+		output
+	}
+	
+	/// Returns the number of keys in the tree
+	/// 
+	/// If other threads are editing the tree, this might already be outdated by the time it returns
+	pub fn len(&self) -> usize {
+		self.len.load(Ordering::Relaxed)
+	}
+	/// Returns true if there are no keys in the tree
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+	
+	/// Moves every key/value pair into a regular StringTree
+	pub fn into_tree(self) -> StringTree<T> {
+		let mut output = StringTree::new();
+		for shard in self.shards {
+			output.extend_from(shard.into_inner().unwrap());
+		}
+		output
+	}
+	
+}
+
+impl<T> Default for ConcurrentStringTree<T> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
@@ -0,0 +1,181 @@
+use crate::*;
+use std::collections::HashMap;
+
+
+
+/// A read-only set of strings stored as a minimal [DAFSA](https://en.wikipedia.org/wiki/Deterministic_acyclic_finite_state_automaton), which is made with `StringTreeSet::minimize()`
+/// 
+/// Unlike a StringTree, states (nodes) are shared between every key that ends with the same set of suffixes, so word lists with lots of common endings (like "-ing" and "-tion") need far fewer states. Because of this, a state doesn't have a single path, so there's no `path()` here. Every key still has a unique ordinal (its position in sorted order), which can be used to look up values in a separate array.
+pub struct Dawg {
+	pub(crate) edge_starts: Vec<u32>, // the start of each state's edges within `edges`, plus one extra entry at the end
+	pub(crate) edges: Vec<(u8, u32)>, // (byte, target state), sorted by byte for each state
+	pub(crate) finals: Vec<bool>, // whether a key ends at each state
+	pub(crate) key_counts: Vec<u32>, // the number of keys that can be reached from each state (including the state itself)
+	pub(crate) root: u32,
+}
+
+impl StringTreeSet {
+	/// Converts this set into a Dawg, which can't be edited but shares every equivalent subtree
+	pub fn minimize(&self) -> Dawg {
+		let tree = &self.tree;
+		let mut output = Dawg {
+			edge_starts: vec!(0),
+			edges: vec!(),
+			finals: vec!(),
+			key_counts: vec!(),
+			root: 0,
+		};
+		// registers each distinct state by its finality and its edges (which point at states that are already registered)
+		let mut register = HashMap::<(bool, Vec<(u8, u32)>), u32>::new();
+		let mut states = vec!(0; tree.node_values.len());
+		// visits every node after all of its children
+		let mut stack = vec!((0, false));
+		while let Some((index, children_done)) = stack.pop() {
+			if !children_done {
+				// the order that children are registered in doesn't matter, so they don't need to be sorted here
+				stack.push((index, true));
+				stack.extend(tree.node_pointers[index as usize].iter().map(|(_, child_index)| (*child_index, false)));
+				continue;
+			}
+			let is_final = tree.node_values.is_some(index as usize);
+			let edges = tree.sorted_children(index).into_iter().map(|(byte, child_index)| (byte, states[child_index as usize])).collect::<Vec<_>>();
+			let next_state = output.finals.len() as u32;
+			let state = *register.entry((is_final, edges)).or_insert_with_key(|(is_final, edges)| {
+				output.finals.push(*is_final);
+				output.key_counts.push(*is_final as u32 + edges.iter().map(|(_, target)| output.key_counts[*target as usize]).sum::<u32>());
+				output.edges.extend_from_slice(edges);
+				output.edge_starts.push(output.edges.len() as u32);
+				next_state
+			});
+			states[index as usize] = state;
+		}
+		output.root = states[0];
+		output.edge_starts.shrink_to_fit();
+		output.edges.shrink_to_fit();
+		output.finals.shrink_to_fit();
+		output.key_counts.shrink_to_fit();
+		output
+	}
+}
+
+impl Dawg {
+	
+	/// Returns true if the key is in the set
+	pub fn contains(&self, key: impl AsRef<str>) -> bool {
+		self.descend(self.root, key.as_ref().as_bytes()).is_some_and(|state| self.finals[state as usize])
+	}
+	
+	/// Returns the position of the key in the sorted list of keys (or None if it isn't in the set)
+	/// 
+	/// Every key has a different ordinal in `0..len()`, so this is a minimal perfect hash that can be used to index a separate array of values
+	pub fn index_of(&self, key: impl AsRef<str>) -> Option<usize> {
+		let mut output = 0;
+		let mut state = self.root;
+		for byte in key.as_ref().bytes() {
+			if self.finals[state as usize] {
+				output += 1;
+			}
+			let edges = self.edges_of(state);
+			let edge_index = edges.binary_search_by_key(&byte, |(edge_byte, _)| *edge_byte).ok()?;
+			output += edges[..edge_index].iter().map(|(_, target)| self.key_counts[*target as usize] as usize).sum::<usize>();
+			state = edges[edge_index].1;
+		}
+		if !self.finals[state as usize] {return None;}
+		Some(output)
+	}
+	/// Returns the key at a given position in the sorted list of keys (or None if `index` is out of bounds), which is the opposite of `index_of()`
+	pub fn key_at(&self, mut index: usize) -> Option<String> {
+		if index >= self.len() {return None;}
+		let mut string_bytes = vec!();
+		let mut state = self.root;
+		loop {
+			if self.finals[state as usize] {
+				if index == 0 {break;}
+				index -= 1;
+			}
+			for &(byte, target) in self.edges_of(state) {
+				let key_count = self.key_counts[target as usize] as usize;
+				if index < key_count {
+					string_bytes.push(byte);
+					state = target;
+					break;
+				}
+				index -= key_count;
+			}
+		}
+		unsafe {
+			// SAFETY: every key in the set is a valid string
+			Some(String::from_utf8_unchecked(string_bytes))
+		}
+	}
+	
+	/// Iterates over every key in the set, sorted by key bytes
+	pub fn iter(&self) -> impl Iterator<Item = String> {
+		self.iter_from(Some(self.root), vec!())
+	}
+	/// Iterates over every key in the set that starts with `prefix`, sorted by key bytes
+	pub fn iter_prefix(&self, prefix: impl AsRef<str>) -> impl Iterator<Item = String> {
+		let prefix = prefix.as_ref();
+		let start_state = self.descend(self.root, prefix.as_bytes());
+		self.iter_from(start_state, prefix.as_bytes().to_vec())
+	}
+	pub(crate) fn iter_from(&self, start_state: Option<u32>, start_path: Vec<u8>) -> impl Iterator<Item = String> {
+		IterableCoroutine(#[coroutine] move || {
+			let Some(start_state) = start_state else {return;};
+			let mut stack = vec!((start_state, start_path));
+			while let Some((state, path)) = stack.pop() {
+				for &(byte, target) in self.edges_of(state).iter().rev() {
+					let mut child_path = path.clone();
+					child_path.push(byte);
+					stack.push((target, child_path));
+				}
+				if self.finals[state as usize] {
+					// SAFETY: states that end a key always have a valid string as their path
+					yield unsafe { String::from_utf8_unchecked(path) };
+				}
+			}
+		})
+	}
+	
+	/// Returns the number of keys in the set
+	pub fn len(&self) -> usize {
+		self.key_counts[self.root as usize] as usize
+	}
+	/// Returns true if there are no keys in the set
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+	/// Returns the number of states, which is at most the number of nodes in the StringTree that this was made from
+	pub fn state_count(&self) -> usize {
+		self.finals.len()
+	}
+	/// Returns the number of bytes that this set has allocated
+	pub fn memory_usage(&self) -> usize {
+		self.edge_starts.capacity() * size_of::<u32>() + self.edges.capacity() * size_of::<(u8, u32)>() + self.finals.capacity() + self.key_counts.capacity() * size_of::<u32>()
+	}
+	
+	pub(crate) fn edges_of(&self, state: u32) -> &[(u8, u32)] {
+		&self.edges[self.edge_starts[state as usize] as usize .. self.edge_starts[state as usize + 1] as usize]
+	}
+	pub(crate) fn descend(&self, mut state: u32, key: &[u8]) -> Option<u32> {
+		for byte in key {
+			let edges = self.edges_of(state);
+			let edge_index = edges.binary_search_by_key(byte, |(edge_byte, _)| *edge_byte).ok()?;
+			state = edges[edge_index].1;
+		}
+		Some(state)
+	}
+	
+}
+
+impl Clone for Dawg {
+	fn clone(&self) -> Self {
+		Self {
+			edge_starts: self.edge_starts.clone(),
+			edges: self.edges.clone(),
+			finals: self.finals.clone(),
+			key_counts: self.key_counts.clone(),
+			root: self.root,
+		}
+	}
+}
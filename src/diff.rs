@@ -0,0 +1,129 @@
+use crate::*;
+use serde::{Serialize, Deserialize};
+
+
+
+/// A single difference between two StringTrees, as yielded by `StringTree::diff()`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change<'a, T> {
+	/// The key is only in the new tree
+	Added(String, &'a T),
+	/// The key is only in the old tree
+	Removed(String, &'a T),
+	/// The key is in both trees with different values (old value, new value)
+	Changed(String, &'a T, &'a T),
+}
+
+impl<'a, T> Change<'a, T> {
+	/// Returns the key that this change is for
+	pub fn key(&self) -> &str {
+		match self {
+			Self::Added(key, _) => key,
+			Self::Removed(key, _) => key,
+			Self::Changed(key, _, _) => key,
+		}
+	}
+}
+
+/// A list of edits that turns one StringTree into another, which can be created with `StringTree::make_patch()` and applied with `StringTree::apply_patch()`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Patch<T> {
+	/// The edits, in the order they are applied
+	pub ops: Vec<PatchOp<T>>,
+}
+
+/// A single edit within a Patch
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PatchOp<T> {
+	/// Sets the value at a key, which covers both added and changed keys
+	Insert(String, T),
+	/// Removes a key
+	Remove(String),
+}
+
+impl<T: Clone> Patch<T> {
+	/// Creates a patch from a list of changes
+	pub fn from_changes<'a>(changes: impl IntoIterator<Item = Change<'a, T>>) -> Self where T: 'a {
+		let ops = changes.into_iter().map(|change| match change {
+			Change::Added(key, value) => PatchOp::Insert(key, value.clone()),
+			Change::Changed(key, _, value) => PatchOp::Insert(key, value.clone()),
+			Change::Removed(key, _) => PatchOp::Remove(key),
+		}).collect();
+		Self {ops}
+	}
+}
+
+impl<T: PartialEq> StringTree<T> {
+	
+	/// Iterates over every difference between this tree (the old tree) and `other` (the new tree), sorted by key bytes
+	/// 
+	/// This walks both trees in lockstep, so only the keys that differ are turned into Strings. If `other` is a clone of this tree (or the other way around), subtrees that haven't been changed since the clone are skipped without being walked, so diffing a big tree with a few changes only visits the changed paths. Trees that aren't clones of each other never share versions, so they are always walked fully.
+	pub fn diff<'a>(&'a self, other: &'a StringTree<T>) -> impl Iterator<Item = Change<'a, T>> {
+		IterableCoroutine(#[coroutine] move || {
+			// each entry is (self index, other index, depth, last byte of the path), and `path` is shared by every node since they are visited depth-first
+			let mut path = vec!();
+			let mut stack = vec!((Some(0), Some(0), 0usize, 0));
+			while let Some((self_index, other_index, depth, byte)) = stack.pop() {
+				if let (Some(self_index), Some(other_index)) = (self_index, other_index)
+					&& self.node_versions[self_index as usize] == other.node_versions[other_index as usize] {continue;}
+				path.truncate(depth.saturating_sub(1));
+				if depth > 0 {
+					path.push(byte);
+				}
+				let self_value = self_index.and_then(|index| self.node_values.get(index as usize));
+				let other_value = other_index.and_then(|index| other.node_values.get(index as usize));
+				let self_children = self_index.map(|index| self.sorted_children(index)).unwrap_or_default();
+				let other_children = other_index.map(|index| other.sorted_children(index)).unwrap_or_default();
+				// push children in reverse order so that they are popped in sorted order
+				let (mut self_i, mut other_i) = (self_children.len(), other_children.len());
+				while self_i > 0 || other_i > 0 {
+					let self_byte = self_i.checked_sub(1).map(|i| self_children[i].0);
+					let other_byte = other_i.checked_sub(1).map(|i| other_children[i].0);
+					let (byte, self_child, other_child) = match (self_byte, other_byte) {
+						(Some(self_byte), Some(other_byte)) if self_byte == other_byte => {
+							self_i -= 1;
+							other_i -= 1;
+							(self_byte, Some(self_children[self_i].1), Some(other_children[other_i].1))
+						}
+						(Some(self_byte), other_byte) if other_byte.is_none_or(|other_byte| self_byte > other_byte) => {
+							self_i -= 1;
+							(self_byte, Some(self_children[self_i].1), None)
+						}
+						_ => {
+							other_i -= 1;
+							(other_children[other_i].0, None, Some(other_children[other_i].1))
+						}
+					};
+					stack.push((self_child, other_child, depth + 1, byte));
+				}
+				// SAFETY: nodes with values always have a valid string as their path
+				match (self_value, other_value) {
+					(Some(self_value), Some(other_value)) => if self_value != other_value {
+						yield Change::Changed(unsafe { String::from_utf8_unchecked(path.clone()) }, self_value, other_value);
+					},
+					(Some(self_value), None) => yield Change::Removed(unsafe { String::from_utf8_unchecked(path.clone()) }, self_value),
+					(None, Some(other_value)) => yield Change::Added(unsafe { String::from_utf8_unchecked(path.clone()) }, other_value),
+					(None, None) => {}
+				}
+			}
+		})
+	}
+	
+	/// Creates a patch that turns this tree into `other` when applied with `apply_patch()`
+	pub fn make_patch(&self, other: &StringTree<T>) -> Patch<T> where T: Clone {
+		Patch::from_changes(self.diff(other))
+	}
+	
+}
+
+impl<T> StringTree<T> {
+	/// Applies every edit in a patch to this tree, in order
+	pub fn apply_patch(&mut self, patch: Patch<T>) {
+		for op in patch.ops {
+			match op {
+				PatchOp::Insert(key, value) => {self.insert(key, value);}
+				PatchOp::Remove(key) => {self.remove(key);}
+			}
+		}
+	}
+}
@@ -0,0 +1,336 @@
+use crate::*;
+use std::collections::VecDeque;
+
+
+
+const NO_STATE: u32 = u32::MAX;
+const NO_VALUE: u32 = u32::MAX;
+const NOT_LISTED: u32 = u32::MAX;
+/// The number of times a free slot can be tried as the first child's slot without fitting before it isn't tried anymore (it can still be used for other children), which keeps dense areas from being searched again and again
+const MAX_FAILURES: u8 = 16;
+
+/// A read-only version of StringTree that is stored as a [double-array trie](https://linux.thai.net/~thep/datrie/datrie.html), which is made with `StringTree::freeze()`
+/// 
+/// Every state (node) has a `base`, and the child of state `s` for byte `c` is state `base[s] + c`, which only exists if `check[base[s] + c] == s`. This makes every step a couple of array reads with no searching through children lists, which makes lookups much faster than StringTree for trees that are built once and read many times.
+pub struct FrozenStringTree<T> {
+	pub(crate) bases: Vec<u32>,
+	pub(crate) checks: Vec<u32>, // the parent of each state, or NO_STATE for unused slots (and the root)
+	pub(crate) child_bytes: Vec<(u8, u8)>, // the smallest and largest byte of each state's children, so that finding the children doesn't need to check all 256 bytes
+	pub(crate) value_indices: Vec<u32>, // the index into `values` for each state, or NO_VALUE if it has no value
+	pub(crate) values: Vec<T>,
+}
+
+impl<T> StringTree<T> {
+	/// Converts this tree into a FrozenStringTree, which can't be edited but has much faster lookups
+	pub fn freeze(mut self) -> FrozenStringTree<T> {
+		let mut output = FrozenStringTree {
+			bases: vec!(0),
+			checks: vec!(NO_STATE),
+			child_bytes: vec!((0, 0)),
+			value_indices: vec!(NO_VALUE),
+			values: vec!(),
+		};
+		let mut free_slots = FreeSlots::new();
+		let mut queue = VecDeque::from([(0, 0)]);
+		while let Some((node_index, state)) = queue.pop_front() {
+			if let Some(value) = self.node_values.take(node_index as usize) {
+				output.value_indices[state as usize] = output.values.len() as u32;
+				output.values.push(value);
+			}
+			let children = self.sorted_children(node_index);
+			if children.is_empty() {continue;}
+			// find the first base where every child's slot is free, by trying each free slot as the first child's slot (and slots past the end if none of them fit)
+			let first_byte = children[0].0 as u32;
+			let mut slot = free_slots.next[0];
+			let base = loop {
+				if slot == 0 {
+					break (output.checks.len() as u32).max(first_byte + 1) - first_byte;
+				}
+				let next_slot = free_slots.next[slot as usize];
+				if slot > first_byte && children.iter().all(|(byte, _)| output.is_free(slot - first_byte + *byte as u32)) {
+					break slot - first_byte;
+				}
+				free_slots.failures[slot as usize] += 1;
+				if free_slots.failures[slot as usize] >= MAX_FAILURES {
+					free_slots.remove(slot);
+				}
+				slot = next_slot;
+			};
+			output.bases[state as usize] = base;
+			output.child_bytes[state as usize] = (children[0].0, children[children.len() - 1].0);
+			for (byte, child_index) in children {
+				let child_state = base + byte as u32;
+				output.grow_to(child_state as usize + 1);
+				free_slots.grow_to(output.checks.len());
+				output.checks[child_state as usize] = state;
+				free_slots.remove(child_state);
+				queue.push_back((child_index, child_state));
+			}
+		}
+		output.shrink_to_fit();
+		output
+	}
+}
+
+/// The unused slots of a FrozenStringTree while it is being built, as a doubly-linked list so that finding a base only tries slots that are actually free
+struct FreeSlots {
+	next: Vec<u32>, // the next free slot after each free slot (or NOT_LISTED), where slot 0 (the root, which is never free) is both the start and the end of the list
+	prev: Vec<u32>, // the previous free slot before each free slot
+	failures: Vec<u8>, // the number of times each slot has been tried as the first child's slot without fitting, see `MAX_FAILURES`
+}
+
+impl FreeSlots {
+	fn new() -> Self {
+		Self {
+			next: vec!(0),
+			prev: vec!(0),
+			failures: vec!(0),
+		}
+	}
+	/// Adds every slot up to `len` to the end of the list
+	fn grow_to(&mut self, len: usize) {
+		for slot in self.next.len() as u32..len as u32 {
+			let last = self.prev[0];
+			self.next.push(0);
+			self.prev.push(last);
+			self.failures.push(0);
+			self.next[last as usize] = slot;
+			self.prev[0] = slot;
+		}
+	}
+	/// Removes a slot from the list, if it is still in it
+	fn remove(&mut self, slot: u32) {
+		let (prev, next) = (self.prev[slot as usize], self.next[slot as usize]);
+		if next == NOT_LISTED {return;}
+		self.next[prev as usize] = next;
+		self.prev[next as usize] = prev;
+		self.next[slot as usize] = NOT_LISTED;
+	}
+}
+
+impl<T> FrozenStringTree<T> {
+	
+	/// Gets a value from a given key (or None)
+	pub fn get(&self, key: impl AsRef<str>) -> Option<&T> {
+		self.root_node().get(key)
+	}
+	/// Returns true if the key has a value
+	pub fn contains_key(&self, key: impl AsRef<str>) -> bool {
+		self.get(key).is_some()
+	}
+	/// Steps further into the tree and returns a node reference (or None)
+	pub fn step<'a>(&'a self, key: impl AsRef<str>) -> Option<FrozenStringTreeNode<'a, T>> {
+		self.root_node().step(key)
+	}
+	pub const fn root_node<'a>(&'a self) -> FrozenStringTreeNode<'a, T> {
+		FrozenStringTreeNode {
+			ref_tree: self,
+			state: 0,
+		}
+	}
+	
+	/// Gets the value of the longest key that is a prefix of `key` (including `key` itself), along with that part of `key` (or None)
+	pub fn longest_match<'a, 'k>(&'a self, key: &'k str) -> Option<(&'k str, &'a T)> {
+		let mut output = self.value_of(0).map(|value| (&key[..0], value));
+		let mut state = 0;
+		for (i, byte) in key.bytes().enumerate() {
+			let Some(next_state) = self.transition(state, byte) else {break;};
+			state = next_state;
+			// values can only exist at char boundaries, so slicing here cannot panic
+			if let Some(value) = self.value_of(state) {
+				output = Some((&key[..i + 1], value));
+			}
+		}
+		output
+	}
+	
+	/// Iterates over every key/value pair in the tree, sorted by key bytes
+	pub fn iter(&self) -> impl Iterator<Item = (String, &T)> {
+		self.iter_from(Some(0), vec!())
+	}
+	/// Iterates over every key/value pair whose key starts with `prefix`, sorted by key bytes
+	pub fn iter_prefix(&self, prefix: impl AsRef<str>) -> impl Iterator<Item = (String, &T)> {
+		let prefix = prefix.as_ref();
+		let start_state = self.step(prefix).map(|node| node.state);
+		self.iter_from(start_state, prefix.as_bytes().to_vec())
+	}
+	pub(crate) fn iter_from(&self, start_state: Option<u32>, start_path: Vec<u8>) -> impl Iterator<Item = (String, &T)> {
+		IterableCoroutine(#[coroutine] move || {
+			let Some(start_state) = start_state else {return;};
+			let mut stack = vec!((start_state, start_path));
+			while let Some((state, path)) = stack.pop() {
+				let children = self.child_states(state).collect::<Vec<_>>();
+				for (byte, child_state) in children.into_iter().rev() {
+					let mut child_path = path.clone();
+					child_path.push(byte);
+					stack.push((child_state, child_path));
+				}
+				if let Some(value) = self.value_of(state) {
+					// SAFETY: states with values always have a valid string as their path
+					yield (unsafe { String::from_utf8_unchecked(path) }, value);
+				}
+			}
+		})
+	}
+	
+	/// Returns the number of keys in the tree
+	pub fn len(&self) -> usize {
+		self.values.len()
+	}
+	/// Returns true if there are no keys in the tree
+	pub fn is_empty(&self) -> bool {
+		self.values.is_empty()
+	}
+	/// Returns the number of bytes that this tree has allocated (not including any memory that the values themselves have allocated)
+	pub fn memory_usage(&self) -> usize {
+		(self.bases.capacity() + self.checks.capacity() + self.value_indices.capacity()) * size_of::<u32>() + self.child_bytes.capacity() * size_of::<(u8, u8)>() + self.values.capacity() * size_of::<T>()
+	}
+	
+	/// Converts this back into a StringTree that can be edited
+	pub fn thaw(mut self) -> StringTree<T> {
+		let mut values = std::mem::take(&mut self.values).into_iter().map(Some).collect::<Vec<_>>();
+		let mut output = StringTree::new();
+		let mut stack = vec!((0, 0)); // (state, node index)
+		while let Some((state, node_index)) = stack.pop() {
+			let value_index = self.value_indices[state as usize];
+			if value_index != NO_VALUE {
+				output.replace_value(node_index, values[value_index as usize].take());
+			}
+			for (byte, child_state) in self.child_states(state) {
+				let child_index = output.push_node(node_index, byte, None);
+				stack.push((child_state, child_index));
+			}
+		}
+		output.check_invariants();
+		output
+	}
+	
+	/// Returns the state that is reached from `state` with `byte` (or None)
+	#[inline]
+	pub(crate) fn transition(&self, state: u32, byte: u8) -> Option<u32> {
+		let base = self.bases[state as usize];
+		if base == 0 {return None;}
+		let next_state = base + byte as u32;
+		if self.checks.get(next_state as usize) == Some(&state) {Some(next_state)} else {None}
+	}
+	/// Returns the (byte, state) pairs of the children of `state`, sorted by byte
+	pub(crate) fn child_states(&self, state: u32) -> impl Iterator<Item = (u8, u32)> + '_ {
+		let base = self.bases[state as usize];
+		let (min_byte, max_byte) = self.child_bytes[state as usize];
+		let bytes = if base == 0 {0..0} else {min_byte as u32..max_byte as u32 + 1};
+		bytes.filter_map(move |byte| {
+			let child_state = base + byte;
+			if self.checks[child_state as usize] == state {Some((byte as u8, child_state))} else {None}
+		})
+	}
+	pub(crate) fn value_of(&self, state: u32) -> Option<&T> {
+		let value_index = self.value_indices[state as usize];
+		if value_index == NO_VALUE {return None;}
+		Some(&self.values[value_index as usize])
+	}
+	fn is_free(&self, state: u32) -> bool {
+		state != 0 && self.checks.get(state as usize).is_none_or(|check| *check == NO_STATE)
+	}
+	fn grow_to(&mut self, len: usize) {
+		if self.checks.len() >= len {return;}
+		self.bases.resize(len, 0);
+		self.checks.resize(len, NO_STATE);
+		self.child_bytes.resize(len, (0, 0));
+		self.value_indices.resize(len, NO_VALUE);
+	}
+	fn shrink_to_fit(&mut self) {
+		self.bases.shrink_to_fit();
+		self.checks.shrink_to_fit();
+		self.child_bytes.shrink_to_fit();
+		self.value_indices.shrink_to_fit();
+		self.values.shrink_to_fit();
+	}
+	
+}
+
+impl<T: Clone> Clone for FrozenStringTree<T> {
+	fn clone(&self) -> Self {
+		Self {
+			bases: self.bases.clone(),
+			checks: self.checks.clone(),
+			child_bytes: self.child_bytes.clone(),
+			value_indices: self.value_indices.clone(),
+			values: self.values.clone(),
+		}
+	}
+}
+
+
+
+/// A reference to a state (node) within a FrozenStringTree
+pub struct FrozenStringTreeNode<'a, T> {
+	pub(crate) ref_tree: &'a FrozenStringTree<T>,
+	pub(crate) state: u32,
+}
+
+impl<'a, T> FrozenStringTreeNode<'a, T> {
+	
+	/// Steps further into the tree and returns the value at the desired position (or None)
+	pub fn get(&self, key: impl AsRef<str>) -> Option<&'a T> {
+		self.step(key)?.value()
+	}
+	/// Steps further into the tree and returns a new node reference (or None)
+	pub fn step(&self, key: impl AsRef<str>) -> Option<FrozenStringTreeNode<'a, T>> {
+		let mut state = self.state;
+		for byte in key.as_ref().bytes() {
+			state = self.ref_tree.transition(state, byte)?;
+		}
+		Some(Self {
+			ref_tree: self.ref_tree,
+			state,
+		})
+	}
+	
+	/// Returns the value at this node (or None)
+	pub fn value(&self) -> Option<&'a T> {
+		self.ref_tree.value_of(self.state)
+	}
+	
+	/// Creates and returns the string that is needed to reach this node from the root node
+	pub fn path(&self) -> String {
+		let mut string_bytes = vec!();
+		let mut state = self.state;
+		while state != 0 {
+			let parent_state = self.ref_tree.checks[state as usize];
+			string_bytes.push((state - self.ref_tree.bases[parent_state as usize]) as u8);
+			state = parent_state;
+		}
+		string_bytes.reverse();
+		unsafe {
+			// SAFETY: this result should be the path of this node, which itself should be a valid string
+			String::from_utf8_unchecked(string_bytes)
+		}
+	}
+	
+	/// Iterates over the children of this node, sorted by bytes
+	/// 
+	/// Note: for multi-byte characters, this does traverse deeper into the tree to ensure that the resulting node will have a valid `path()`
+	pub fn children(&self) -> impl Iterator<Item = FrozenStringTreeNode<'a, T>> + use<'a, T> {
+		let ref_tree = self.ref_tree;
+		let start_state = self.state;
+		IterableCoroutine(#[coroutine] move || {
+			let mut stack = ref_tree.child_states(start_state).collect::<Vec<_>>().into_iter().rev()
+				.map(|(byte, state)| (state, (byte.leading_ones() as usize).saturating_sub(1)))
+				.collect::<Vec<_>>();
+			while let Some((state, remaining_bytes)) = stack.pop() {
+				if remaining_bytes == 0 {
+					yield FrozenStringTreeNode {ref_tree, state};
+					continue;
+				}
+				let children = ref_tree.child_states(state).collect::<Vec<_>>();
+				stack.extend(children.into_iter().rev().map(|(_, child_state)| (child_state, remaining_bytes - 1)));
+			}
+		})
+	}
+	
+	/// Iterates over every key/value pair below this node (including this node), sorted by key bytes
+	pub fn iter(&self) -> impl Iterator<Item = (String, &'a T)> + use<'a, T> {
+		self.ref_tree.iter_from(Some(self.state), self.path().into_bytes())
+	}
+	
+}
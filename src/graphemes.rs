@@ -0,0 +1,84 @@
+use crate::*;
+use unicode_segmentation::UnicodeSegmentation;
+
+
+
+impl<'a, T> StringTreeNode<'a, T> {
+	
+	/// Iterates over every grapheme cluster that can come next after this node, along with the node at the end of that grapheme
+	/// 
+	/// This is like `children()`, but keeps going across multiple chars until a grapheme boundary, so things like "👍🏽" or "e" + combining accent are yielded as a single entry. A grapheme ends wherever a key ends or a following char starts a new grapheme, so both "👍" and "👍🏽" can be yielded if both are used.
+	/// 
+	/// Note: grapheme boundaries are found using only the part of the path after this node, so this node should be at a grapheme boundary itself
+	pub fn grapheme_children(&self) -> impl Iterator<Item = (String, StringTreeNode<'a, T>)> + use<'a, T> {
+		let ref_tree = self.ref_tree;
+		let start_index = self.index;
+		IterableCoroutine(#[coroutine] move || {
+			let mut stack = ref_tree.next_chars(start_index).into_iter().rev()
+				.map(|(char, index)| (String::from(char), index))
+				.collect::<Vec<_>>();
+			while let Some((grapheme, index)) = stack.pop() {
+				let next_chars = ref_tree.next_chars(index);
+				let mut extending_chars = vec!();
+				let mut is_end = ref_tree.node_values.is_some(index as usize);
+				for (char, child_index) in next_chars {
+					let mut extended = grapheme.clone();
+					extended.push(char);
+					// boundaries only depend on the chars before them, so a boundary here stays a boundary no matter what comes after
+					if extended.graphemes(true).nth(1).is_some() {
+						is_end = true;
+					} else {
+						extending_chars.push((extended, child_index));
+					}
+				}
+				stack.extend(extending_chars.into_iter().rev());
+				if is_end {
+					yield (grapheme, StringTreeNode {ref_tree, index});
+				}
+			}
+		})
+	}
+	
+	/// Steps exactly one grapheme cluster further into the tree and returns the node at the end of it (or None)
+	/// 
+	/// This returns None if `grapheme` isn't a single grapheme cluster, or if it isn't one of the `grapheme_children()` of this node (for example "👍" when the only key below this node is "👍🏽")
+	pub fn step_grapheme(&self, grapheme: &str) -> Option<StringTreeNode<'a, T>> {
+		let mut graphemes = grapheme.graphemes(true);
+		if graphemes.next().is_none() || graphemes.next().is_some() {return None;}
+		let index = self.ref_tree.descend(self.index, grapheme.as_bytes())?;
+		let is_end = self.ref_tree.node_values.is_some(index as usize) || self.ref_tree.next_chars(index).into_iter().any(|(char, _)| {
+			let mut extended = grapheme.to_string();
+			extended.push(char);
+			extended.graphemes(true).nth(1).is_some()
+		});
+		if !is_end {return None;}
+		Some(StringTreeNode {
+			ref_tree: self.ref_tree,
+			index,
+		})
+	}
+	
+}
+
+impl<T> StringTree<T> {
+	/// Returns every char that can come directly after `node` along with the node at the end of that char, sorted by bytes
+	pub(crate) fn next_chars(&self, node: u32) -> Vec<(char, u32)> {
+		let mut output = vec!();
+		let mut stack = self.sorted_children(node).into_iter().rev().map(|(byte, index)| (vec!(byte), index)).collect::<Vec<_>>();
+		while let Some((bytes, index)) = stack.pop() {
+			match str::from_utf8(&bytes) {
+				Ok(char_str) => output.push((char_str.chars().next().unwrap(), index)),
+				Err(error) if error.error_len().is_none() => {
+					// the char isn't complete yet, so keep going
+					for (byte, child_index) in self.sorted_children(index).into_iter().rev() {
+						let mut child_bytes = bytes.clone();
+						child_bytes.push(byte);
+						stack.push((child_bytes, child_index));
+					}
+				}
+				Err(_) => {} // invalid UTF-8, which can't happen below a node that is at a char boundary
+			}
+		}
+		output
+	}
+}
@@ -0,0 +1,172 @@
+use crate::*;
+use ipnet::IpNet;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+
+
+/// A longest-prefix-match table for IPv4 and IPv6 CIDR prefixes, stored as SequenceTrees that branch on each bit of the address
+pub struct IpPrefixTree<T> {
+	pub(crate) v4: SequenceTree<bool, T>,
+	pub(crate) v6: SequenceTree<bool, T>,
+	pub(crate) len: usize,
+}
+
+impl<T> IpPrefixTree<T> {
+	
+	/// Creates a new, empty IpPrefixTree
+	pub fn new() -> Self {
+		Self {
+			v4: SequenceTree::new(),
+			v6: SequenceTree::new(),
+			len: 0,
+		}
+	}
+	/// Creates a new IpPrefixTree with a given list of prefix/value pairs
+	pub fn from<I: IntoIterator<Item = (IpNet, T)>>(source: I) -> Self {
+		let mut output = Self::new();
+		for (prefix, value) in source {
+			output.insert(prefix, value);
+		}
+		output
+	}
+	
+	/// Inserts a prefix/value pair into the tree, and returns the previous value if it exists
+	/// 
+	/// Any host bits of `prefix` are ignored, so "10.1.2.3/8" is the same as "10.0.0.0/8"
+	pub fn insert(&mut self, prefix: IpNet, value: T) -> Option<T> {
+		let output = self.tree_mut(prefix.addr()).insert(prefix_bits(prefix), value);
+		if output.is_none() {
+			self.len += 1;
+		}
+		output
+	}
+	/// Gets the value of an exact prefix (or None)
+	pub fn get(&self, prefix: IpNet) -> Option<&T> {
+		self.tree(prefix.addr()).get(prefix_bits(prefix))
+	}
+	/// Gets the value of an exact prefix as mut (or None)
+	pub fn get_mut(&mut self, prefix: IpNet) -> Option<&mut T> {
+		self.tree_mut(prefix.addr()).get_mut(prefix_bits(prefix))
+	}
+	/// Removes and returns the value of an exact prefix (or None)
+	pub fn remove(&mut self, prefix: IpNet) -> Option<T> {
+		let output = self.tree_mut(prefix.addr()).remove(prefix_bits(prefix));
+		if output.is_some() {
+			self.len -= 1;
+		}
+		output
+	}
+	
+	/// Returns the most specific prefix that contains `addr` along with its value (or None)
+	pub fn longest_match(&self, addr: IpAddr) -> Option<(IpNet, &T)> {
+		self.covering_inner(addr, max_prefix_len(addr)).pop()
+	}
+	/// Iterates over every prefix that contains `prefix` (including `prefix` itself), from least to most specific
+	pub fn covering(&self, prefix: IpNet) -> impl Iterator<Item = (IpNet, &T)> {
+		self.covering_inner(prefix.addr(), prefix.prefix_len()).into_iter()
+	}
+	fn covering_inner(&self, addr: IpAddr, prefix_len: u8) -> Vec<(IpNet, &T)> {
+		let tree = self.tree(addr);
+		let bits = address_bits(addr);
+		let mut output = vec!();
+		let mut curr_index = 0;
+		for len in 0..=prefix_len {
+			if len > 0 {
+				let bit = bits >> (128 - len as u32) & 1 == 1;
+				let Some(next_index) = tree.child_index(curr_index, bit) else {break;};
+				curr_index = next_index;
+			}
+			if let Some(value) = tree.node_values.get(curr_index as usize) {
+				output.push((IpNet::new(addr, len).unwrap().trunc(), value));
+			}
+		}
+		output
+	}
+	/// Iterates over every prefix that is contained by `prefix` (including `prefix` itself), sorted by network address and then by prefix length
+	pub fn covered_by(&self, prefix: IpNet) -> impl Iterator<Item = (IpNet, &T)> {
+		let addr = prefix.addr();
+		self.tree(addr).iter_prefix(prefix_bits(prefix)).map(move |(bits, value)| (bits_to_prefix(addr, &bits), value))
+	}
+	
+	/// Iterates over every prefix/value pair, with all IPv4 prefixes before all IPv6 prefixes
+	pub fn iter(&self) -> impl Iterator<Item = (IpNet, &T)> {
+		let v4_addr = IpAddr::V4(Ipv4Addr::UNSPECIFIED);
+		let v6_addr = IpAddr::V6(Ipv6Addr::UNSPECIFIED);
+		let v4_iter = self.v4.iter().map(move |(bits, value)| (bits_to_prefix(v4_addr, &bits), value));
+		let v6_iter = self.v6.iter().map(move |(bits, value)| (bits_to_prefix(v6_addr, &bits), value));
+		v4_iter.chain(v6_iter)
+	}
+	
+	/// Returns the number of prefixes in the tree
+	pub const fn len(&self) -> usize {
+		self.len
+	}
+	/// Returns true if there are no prefixes in the tree
+	pub const fn is_empty(&self) -> bool {
+		self.len == 0
+	}
+	
+	fn tree(&self, addr: IpAddr) -> &SequenceTree<bool, T> {
+		match addr {
+			IpAddr::V4(_) => &self.v4,
+			IpAddr::V6(_) => &self.v6,
+		}
+	}
+	fn tree_mut(&mut self, addr: IpAddr) -> &mut SequenceTree<bool, T> {
+		match addr {
+			IpAddr::V4(_) => &mut self.v4,
+			IpAddr::V6(_) => &mut self.v6,
+		}
+	}
+	
+}
+
+impl<T> Default for IpPrefixTree<T> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<T: Clone> Clone for IpPrefixTree<T> {
+	fn clone(&self) -> Self {
+		Self {
+			v4: self.v4.clone(),
+			v6: self.v6.clone(),
+			len: self.len,
+		}
+	}
+}
+
+
+
+/// Returns the bits of an address, aligned to the most significant bit of a u128
+fn address_bits(addr: IpAddr) -> u128 {
+	match addr {
+		IpAddr::V4(addr) => (u32::from(addr) as u128) << 96,
+		IpAddr::V6(addr) => u128::from(addr),
+	}
+}
+
+fn max_prefix_len(addr: IpAddr) -> u8 {
+	match addr {
+		IpAddr::V4(_) => 32,
+		IpAddr::V6(_) => 128,
+	}
+}
+
+/// Returns the first `prefix_len` bits of a prefix, which is its key in the tree
+fn prefix_bits(prefix: IpNet) -> Vec<bool> {
+	let bits = address_bits(prefix.addr());
+	(1..=prefix.prefix_len() as u32).map(|i| bits >> (128 - i) & 1 == 1).collect()
+}
+
+/// Turns a key back into a prefix, where `family` is any address of the right family
+fn bits_to_prefix(family: IpAddr, bits: &[bool]) -> IpNet {
+	let value = bits.iter().enumerate().fold(0u128, |value, (i, &bit)| value | ((bit as u128) << (127 - i)));
+	let addr = match family {
+		IpAddr::V4(_) => IpAddr::V4(Ipv4Addr::from((value >> 96) as u32)),
+		IpAddr::V6(_) => IpAddr::V6(Ipv6Addr::from(value)),
+	};
+	// the length of a key can never be more than the max prefix length
+	IpNet::new(addr, bits.len() as u8).unwrap()
+}
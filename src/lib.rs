@@ -1,5 +1,4 @@
 #![feature(coroutines, coroutine_trait)]
-#![feature(portable_simd)]
 
 
 
@@ -12,6 +11,73 @@ pub use string_tree_node::*;
 /// A mutable reference to a node within a StringTree, allows for viewing, traversal, editing, etc
 pub mod string_tree_node_mut;
 pub use string_tree_node_mut::*;
+/// An immutable StringTree where edits create new versions that share unchanged nodes
+pub mod persistent_string_tree;
+pub use persistent_string_tree::*;
+/// A thread-safe StringTree with one lock per first-byte subtree
+pub mod concurrent_string_tree;
+pub use concurrent_string_tree::*;
+/// Parallel iteration and bulk building with rayon
+#[cfg(feature = "rayon")]
+pub mod parallel;
+/// Renumbering nodes into depth-first or breadth-first order
+pub mod compact;
+pub use compact::*;
+/// Lockstep merging, intersection and difference between StringTrees
+pub mod merge;
+/// Structural diffs and patches between StringTrees
+pub mod diff;
+pub use diff::*;
+/// A set of strings, backed by a StringTree
+pub mod string_tree_set;
+pub use string_tree_set::*;
+/// A StringTree that holds multiple values per key
+pub mod string_tree_multi_map;
+pub use string_tree_multi_map::*;
+/// A view of a node that traverses the tree in separator-delimited segments instead of chars
+pub mod segment_view;
+pub use segment_view::*;
+/// A URL router with path parameters and catch-alls, built on a StringTree
+pub mod router;
+pub use router::*;
+/// Integrity checking of a StringTree's internal invariants
+pub mod validate;
+pub use validate::*;
+/// A tree with arbitrary byte string keys, which don't have to be valid UTF-8
+pub mod byte_string_tree;
+pub use byte_string_tree::*;
+/// Per-node value storage that doesn't use any memory for zero-sized values
+mod node_values;
+pub(crate) use node_values::*;
+/// A tree keyed by sequences of any symbol type, which StringTree is the byte version of
+pub mod sequence_tree;
+pub use sequence_tree::*;
+/// A StringTree with case-insensitive or otherwise normalized keys
+pub mod normalized_string_tree;
+pub use normalized_string_tree::*;
+/// Grapheme-cluster-aware traversal
+#[cfg(feature = "unicode")]
+pub mod graphemes;
+/// A read-only double-array version of StringTree with faster lookups
+pub mod frozen_string_tree;
+pub use frozen_string_tree::*;
+/// A longest-prefix-match table for IP/CIDR prefixes
+#[cfg(feature = "ipnet")]
+pub mod ip_prefix_tree;
+#[cfg(feature = "ipnet")]
+pub use ip_prefix_tree::*;
+/// A read-only LOUDS-encoded version of StringTree with minimal memory usage
+pub mod succinct_string_tree;
+pub use succinct_string_tree::*;
+/// A minimal automaton for sets of strings that shares common suffixes
+pub mod dawg;
+pub use dawg::*;
+/// Conversion between StringTree and fst maps
+#[cfg(feature = "fst")]
+pub mod string_tree_fst;
+/// A read-only StringTree that is built at compile time by the `string_tree!` macro
+pub mod static_string_tree;
+pub use static_string_tree::{StaticStringTree, StaticStringTreeNode, StaticNode};
 #[cfg(test)]
 mod tests;
 
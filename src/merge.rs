@@ -0,0 +1,112 @@
+use crate::*;
+use std::mem;
+
+
+
+impl<T> StringTree<T> {
+	
+	/// Merges every key/value pair from `other` into this tree, using `resolve(key, self_value, other_value)` to decide the value of keys that are in both trees
+	/// 
+	/// This walks both trees in lockstep, and subtrees that only exist in `other` are moved over as a whole
+	pub fn merge(&mut self, mut other: StringTree<T>, mut resolve: impl FnMut(&str, T, T) -> T) {
+		// each entry is a node in both trees along with its depth and the byte that leads to it, so that one path can be shared by every node
+		let mut path = vec!();
+		let mut stack = vec!((0, 0, 0usize, 0));
+		while let Some((self_index, other_index, depth, byte)) = stack.pop() {
+			if depth > 0 {
+				path.truncate(depth - 1);
+				path.push(byte);
+			}
+			if let Some(other_value) = other.node_values.take(other_index as usize) {
+				let new_value = match self.replace_value(self_index, None) {
+					// SAFETY: nodes with values always have a valid string as their path
+					Some(self_value) => resolve(unsafe { str::from_utf8_unchecked(&path) }, self_value, other_value),
+					None => other_value,
+				};
+				self.replace_value(self_index, Some(new_value));
+			}
+			let mut next_entries = vec!();
+			for (byte, other_child) in other.sorted_children(other_index) {
+				match self.child_index(self_index, byte) {
+					Some(self_child) => next_entries.push((self_child, other_child, depth + 1, byte)),
+					None => self.graft_subtree(self_index, byte, &mut other, other_child),
+				}
+			}
+			stack.extend(next_entries.into_iter().rev());
+		}
+		self.check_invariants();
+	}
+	/// Moves every key/value pair from `other` into this tree, replacing the values of keys that are in both trees
+	pub fn extend_from(&mut self, other: StringTree<T>) {
+		self.merge(other, |_, _, other_value| other_value);
+	}
+	/// Removes every key that isn't also in `other`
+	/// 
+	/// This walks both trees in lockstep, and subtrees that don't exist in `other` are dropped as a whole
+	pub fn intersect_with<U>(&mut self, other: &StringTree<U>) {
+		self.filter_by(other, true);
+	}
+	/// Removes every key that is also in `other`
+	/// 
+	/// This walks both trees in lockstep, and subtrees that don't exist in `other` are kept as a whole
+	pub fn difference<U>(&mut self, other: &StringTree<U>) {
+		self.filter_by(other, false);
+	}
+	
+	/// Removes every key where being in `other` doesn't match `keep_matched`, along with any nodes that are no longer needed
+	/// 
+	/// This edits the tree in place, so the nodes that are kept also keep their NodeIds
+	fn filter_by<U>(&mut self, other: &StringTree<U>, keep_matched: bool) {
+		// each entry is a node in this tree along with the node in `other` at the same path, and every node is visited again after its children
+		let mut stack = vec!((0, 0, false));
+		while let Some((self_index, other_index, children_done)) = stack.pop() {
+			if children_done {
+				// remove the node if nothing was kept below it
+				if self.node_values.is_none(self_index as usize) && self.node_pointers[self_index as usize].is_empty() {
+					self.free_node(self_index);
+				}
+				continue;
+			}
+			let in_other = other.node_values.is_some(other_index as usize);
+			if in_other != keep_matched && self.node_values.is_some(self_index as usize) {
+				self.replace_value(self_index, None);
+			}
+			for (byte, self_child) in self.sorted_children(self_index).into_iter().rev() {
+				match other.child_index(other_index, byte) {
+					Some(other_child) => {
+						stack.push((self_child, other_child, true));
+						stack.push((self_child, other_child, false));
+					}
+					None => if keep_matched {self.remove_subtree(self_child);},
+				}
+			}
+		}
+		self.check_invariants();
+	}
+	
+	/// Removes the node at `index` along with its whole subtree
+	fn remove_subtree(&mut self, index: u32) {
+		let mut subtree = vec!();
+		let mut stack = vec!(index);
+		while let Some(index) = stack.pop() {
+			subtree.push(index);
+			stack.extend(self.node_pointers[index as usize].iter().map(|(_, child_index)| *child_index));
+		}
+		// children are always freed before their parents
+		for index in subtree.into_iter().rev() {
+			self.free_node(index);
+		}
+	}
+	
+	/// Moves the subtree at `source_index` out of `source` and appends it as the child of `parent` that is reached with `byte`
+	pub(crate) fn graft_subtree(&mut self, parent: u32, byte: u8, source: &mut StringTree<T>, source_index: u32) {
+		let mut stack = vec!((parent, byte, source_index));
+		while let Some((parent, byte, source_index)) = stack.pop() {
+			let index = self.push_node(parent, byte, source.node_values.take(source_index as usize));
+			let mut children = mem::take(&mut source.node_pointers[source_index as usize]);
+			children.sort_unstable_by_key(|(child_byte, _)| *child_byte);
+			stack.extend(children.into_iter().rev().map(|(child_byte, child_index)| (index, child_byte, child_index)));
+		}
+	}
+	
+}
@@ -0,0 +1,171 @@
+use std::{mem, ptr::NonNull};
+
+
+
+/// The value slot of every node in a tree, indexed by node index
+/// 
+/// Values that take up memory are stored as a `Vec<Option<T>>`, but zero-sized values (like the `()` of a StringTreeSet) only need to store whether each node has a value, so they are stored as one bit per node instead of a whole `Option<T>` byte
+pub(crate) struct NodeValues<T> {
+	slots: Vec<Option<T>>, // always empty when T is zero-sized
+	bits: Vec<u64>, // whether each node has a value, only used when T is zero-sized
+	len: usize,
+}
+
+impl<T> NodeValues<T> {
+	
+	const ZERO_SIZED: bool = mem::size_of::<T>() == 0;
+	
+	pub(crate) const fn new() -> Self {
+		Self {
+			slots: Vec::new(),
+			bits: Vec::new(),
+			len: 0,
+		}
+	}
+	pub(crate) fn with_capacity(capacity: usize) -> Self {
+		let mut output = Self::new();
+		output.reserve(capacity);
+		output
+	}
+	
+	pub(crate) const fn len(&self) -> usize {
+		self.len
+	}
+	/// Returns the number of bytes that have been allocated
+	pub(crate) fn allocated_bytes(&self) -> usize {
+		self.slots.capacity() * mem::size_of::<Option<T>>() + self.bits.capacity() * mem::size_of::<u64>()
+	}
+	pub(crate) fn reserve(&mut self, additional: usize) {
+		if Self::ZERO_SIZED {
+			self.bits.reserve((self.len + additional).div_ceil(64).saturating_sub(self.bits.len()));
+		} else {
+			self.slots.reserve(additional);
+		}
+	}
+	pub(crate) fn shrink_to_fit(&mut self) {
+		self.slots.shrink_to_fit();
+		self.bits.shrink_to_fit();
+	}
+	
+	/// Adds a slot for a new node at the end
+	pub(crate) fn push(&mut self, value: Option<T>) {
+		if Self::ZERO_SIZED {
+			if self.len.is_multiple_of(64) {
+				self.bits.push(0);
+			}
+			self.len += 1;
+			self.set_bit(self.len - 1, value);
+		} else {
+			self.slots.push(value);
+			self.len += 1;
+		}
+	}
+	/// Removes the slot of the last node and returns its value if it has one
+	pub(crate) fn pop(&mut self) -> Option<T> {
+		if self.len == 0 {return None;}
+		let output = self.take(self.len - 1);
+		self.len -= 1;
+		if Self::ZERO_SIZED {
+			self.bits.truncate(self.len.div_ceil(64));
+		} else {
+			self.slots.pop();
+		}
+		output
+	}
+	
+	pub(crate) fn is_some(&self, index: usize) -> bool {
+		if Self::ZERO_SIZED {
+			self.get_bit(index)
+		} else {
+			self.slots[index].is_some()
+		}
+	}
+	pub(crate) fn is_none(&self, index: usize) -> bool {
+		!self.is_some(index)
+	}
+	pub(crate) fn get(&self, index: usize) -> Option<&T> {
+		if Self::ZERO_SIZED {
+			// SAFETY: a dangling pointer is a valid reference to a zero-sized value, and a value has been stored here
+			self.get_bit(index).then(|| unsafe { NonNull::dangling().as_ref() })
+		} else {
+			self.slots[index].as_ref()
+		}
+	}
+	pub(crate) fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+		if Self::ZERO_SIZED {
+			// SAFETY: a dangling pointer is a valid reference to a zero-sized value, and a value has been stored here
+			self.get_bit(index).then(|| unsafe { NonNull::dangling().as_mut() })
+		} else {
+			self.slots[index].as_mut()
+		}
+	}
+	/// Sets the value of a slot, and returns the previous value if it exists
+	pub(crate) fn replace(&mut self, index: usize, value: Option<T>) -> Option<T> {
+		if Self::ZERO_SIZED {
+			let had_value = self.get_bit(index);
+			self.set_bit(index, value);
+			// SAFETY: a value was stored here, and zero-sized values can be read from any aligned pointer
+			had_value.then(|| unsafe { NonNull::dangling().read() })
+		} else {
+			mem::replace(&mut self.slots[index], value)
+		}
+	}
+	pub(crate) fn take(&mut self, index: usize) -> Option<T> {
+		self.replace(index, None)
+	}
+	
+	/// Iterates over every value as mut (in no particular order)
+	#[cfg(feature = "rayon")]
+	pub(crate) fn par_iter_mut(&mut self) -> impl rayon::iter::ParallelIterator<Item = &mut T> where T: Send {
+		use rayon::prelude::*;
+		let zero_sized_count = self.bits.iter().map(|bits| bits.count_ones() as usize).sum::<usize>();
+		self.slots.par_iter_mut().filter_map(Option::as_mut)
+			// SAFETY: a dangling pointer is a valid reference to a zero-sized value
+			.chain((0..zero_sized_count).into_par_iter().map(|_| unsafe { NonNull::dangling().as_mut() }))
+	}
+	
+	fn get_bit(&self, index: usize) -> bool {
+		if index >= self.len {panic!("index out of bounds: the length is {} but the index is {}", self.len, index);}
+		self.bits[index / 64] & (1 << (index % 64)) != 0
+	}
+	/// Stores whether a zero-sized slot has a value, and forgets the value itself since `replace()` can make it again from nothing
+	fn set_bit(&mut self, index: usize, value: Option<T>) {
+		if index >= self.len {panic!("index out of bounds: the length is {} but the index is {}", self.len, index);}
+		if let Some(value) = value {
+			mem::forget(value);
+			self.bits[index / 64] |= 1 << (index % 64);
+		} else {
+			self.bits[index / 64] &= !(1 << (index % 64));
+		}
+	}
+	
+}
+
+impl<T: Clone> Clone for NodeValues<T> {
+	fn clone(&self) -> Self {
+		if Self::ZERO_SIZED {
+			let mut output = Self::with_capacity(self.len);
+			for index in 0..self.len {
+				output.push(self.get(index).cloned());
+			}
+			output
+		} else {
+			Self {
+				slots: self.slots.clone(),
+				bits: vec!(),
+				len: self.len,
+			}
+		}
+	}
+}
+
+impl<T> Drop for NodeValues<T> {
+	fn drop(&mut self) {
+		// zero-sized values were forgotten when they were stored, so they still need to be dropped
+		if Self::ZERO_SIZED && mem::needs_drop::<T>() {
+			for index in 0..self.len {
+				self.take(index);
+			}
+		}
+	}
+}
@@ -0,0 +1,175 @@
+use crate::*;
+use std::borrow::Cow;
+
+
+
+/// Decides which keys are treated as the same key by a NormalizedStringTree
+pub trait KeyNormalizer {
+	/// Returns the normalized form of a key, which is what the tree is actually keyed by
+	fn normalize<'a>(&self, key: &'a str) -> Cow<'a, str>;
+}
+
+impl<F: Fn(&str) -> String> KeyNormalizer for F {
+	fn normalize<'a>(&self, key: &'a str) -> Cow<'a, str> {
+		Cow::Owned(self(key))
+	}
+}
+
+/// Makes keys case-insensitive for ASCII letters only, which is enough for things like HTTP header names
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AsciiCaseFold;
+
+impl KeyNormalizer for AsciiCaseFold {
+	fn normalize<'a>(&self, key: &'a str) -> Cow<'a, str> {
+		if key.bytes().any(|byte| byte.is_ascii_uppercase()) {
+			Cow::Owned(key.to_ascii_lowercase())
+		} else {
+			Cow::Borrowed(key)
+		}
+	}
+}
+
+/// Makes keys case-insensitive using full Unicode case folding (so "STRASSE" and "straße" are the same key)
+#[cfg(feature = "unicode")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UnicodeCaseFold;
+
+#[cfg(feature = "unicode")]
+impl KeyNormalizer for UnicodeCaseFold {
+	fn normalize<'a>(&self, key: &'a str) -> Cow<'a, str> {
+		Cow::Owned(caseless::default_case_fold_str(key))
+	}
+}
+
+/// Treats keys as the same if they have the same NFC form (so a precomposed "é" and "e" + combining accent are the same key)
+#[cfg(feature = "unicode")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Nfc;
+
+#[cfg(feature = "unicode")]
+impl KeyNormalizer for Nfc {
+	fn normalize<'a>(&self, key: &'a str) -> Cow<'a, str> {
+		use unicode_normalization::{is_nfc_quick, IsNormalized, UnicodeNormalization};
+		if is_nfc_quick(key.chars()) == IsNormalized::Yes {return Cow::Borrowed(key);}
+		Cow::Owned(key.nfc().collect())
+	}
+}
+
+/// Treats keys as the same if they have the same NFKC form, which also merges compatibility characters (so "ﬁ" and "fi" are the same key)
+#[cfg(feature = "unicode")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Nfkc;
+
+#[cfg(feature = "unicode")]
+impl KeyNormalizer for Nfkc {
+	fn normalize<'a>(&self, key: &'a str) -> Cow<'a, str> {
+		use unicode_normalization::{is_nfkc_quick, IsNormalized, UnicodeNormalization};
+		if is_nfkc_quick(key.chars()) == IsNormalized::Yes {return Cow::Borrowed(key);}
+		Cow::Owned(key.nfkc().collect())
+	}
+}
+
+
+
+/// A StringTree where keys are normalized before every operation, while the originally inserted spelling of each key is kept with its value
+/// 
+/// Note: prefix queries normalize the prefix by itself, so normalizers that can change across a char boundary (like NFC with combining marks) may not match every key that starts with the given prefix
+pub struct NormalizedStringTree<T, N: KeyNormalizer> {
+	pub(crate) tree: StringTree<(String, T)>,
+	pub(crate) normalizer: N,
+}
+
+impl<T> StringTree<T> {
+	/// Creates a new, empty tree that normalizes every key with `normalizer`, see NormalizedStringTree
+	pub fn with_normalizer<N: KeyNormalizer>(normalizer: N) -> NormalizedStringTree<T, N> {
+		NormalizedStringTree::new(normalizer)
+	}
+}
+
+impl<T, N: KeyNormalizer> NormalizedStringTree<T, N> {
+	
+	/// Creates a new, empty NormalizedStringTree
+	pub fn new(normalizer: N) -> Self {
+		Self {
+			tree: StringTree::new(),
+			normalizer,
+		}
+	}
+	
+	/// Inserts a key/value pair into the tree, and returns the previous value if the normalized key already exists
+	/// 
+	/// If the normalized key already exists, the original spelling of the key is kept (the same as std's HashMap)
+	pub fn insert(&mut self, key: impl AsRef<str>, value: T) -> Option<T> {
+		let key = key.as_ref();
+		let normalized_key = self.normalizer.normalize(key);
+		if let Some((_, existing_value)) = self.tree.get_mut(&normalized_key) {
+			return Some(std::mem::replace(existing_value, value));
+		}
+		self.tree.insert(normalized_key, (key.to_string(), value));
+		None
+	}
+	/// Gets a value from a given key (or None)
+	pub fn get(&self, key: impl AsRef<str>) -> Option<&T> {
+		self.get_key_value(key).map(|(_, value)| value)
+	}
+	/// Gets a value from a given key (or None)
+	pub fn get_mut(&mut self, key: impl AsRef<str>) -> Option<&mut T> {
+		let normalized_key = self.normalizer.normalize(key.as_ref());
+		self.tree.get_mut(normalized_key).map(|(_, value)| value)
+	}
+	/// Gets the originally inserted spelling of a given key along with its value (or None)
+	pub fn get_key_value(&self, key: impl AsRef<str>) -> Option<(&str, &T)> {
+		let normalized_key = self.normalizer.normalize(key.as_ref());
+		self.tree.get(normalized_key).map(|(original_key, value)| (original_key.as_str(), value))
+	}
+	/// Returns true if the key has a value
+	pub fn contains_key(&self, key: impl AsRef<str>) -> bool {
+		self.get_key_value(key).is_some()
+	}
+	/// Removes and returns a value from a given key (or None if there was no value at the given key)
+	pub fn remove(&mut self, key: impl AsRef<str>) -> Option<T> {
+		self.remove_entry(key).map(|(_, value)| value)
+	}
+	/// Removes and returns the originally inserted spelling of a given key along with its value (or None)
+	pub fn remove_entry(&mut self, key: impl AsRef<str>) -> Option<(String, T)> {
+		let normalized_key = self.normalizer.normalize(key.as_ref());
+		self.tree.remove(normalized_key)
+	}
+	
+	/// Steps further into the tree with the normalized form of `key` and returns a node reference (or None)
+	/// 
+	/// The node's `path()` is the normalized path, and its value holds the original spelling of the key
+	pub fn step<'a>(&'a self, key: impl AsRef<str>) -> Option<StringTreeNode<'a, (String, T)>> {
+		let normalized_key = self.normalizer.normalize(key.as_ref());
+		self.tree.step(normalized_key)
+	}
+	
+	/// Iterates over the originally inserted spelling of every key and its value, sorted by the bytes of the normalized keys
+	pub fn iter(&self) -> impl Iterator<Item = (&str, &T)> {
+		self.tree.iter().map(|(_, (original_key, value))| (original_key.as_str(), value))
+	}
+	/// Iterates over every key/value pair whose normalized key starts with the normalized form of `prefix`, sorted by the bytes of the normalized keys
+	pub fn iter_prefix(&self, prefix: impl AsRef<str>) -> impl Iterator<Item = (&str, &T)> {
+		let normalized_prefix = self.normalizer.normalize(prefix.as_ref()).into_owned();
+		self.tree.iter_prefix(normalized_prefix).map(|(_, (original_key, value))| (original_key.as_str(), value))
+	}
+	
+	/// Returns the normalizer that this tree uses
+	pub const fn normalizer(&self) -> &N {
+		&self.normalizer
+	}
+	/// Returns the underlying tree, which is keyed by the normalized keys and holds the original keys with their values
+	pub const fn as_tree(&self) -> &StringTree<(String, T)> {
+		&self.tree
+	}
+	
+}
+
+impl<T: Clone, N: KeyNormalizer + Clone> Clone for NormalizedStringTree<T, N> {
+	fn clone(&self) -> Self {
+		Self {
+			tree: self.tree.clone(),
+			normalizer: self.normalizer.clone(),
+		}
+	}
+}
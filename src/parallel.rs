@@ -0,0 +1,126 @@
+use crate::*;
+use rayon::prelude::*;
+use std::mem;
+
+
+
+impl<T: Sync> StringTree<T> {
+	
+	/// Iterates over every key/value pair in the tree in parallel, where each child subtree of the root node is handled as a separate piece of work
+	/// 
+	/// Collecting this (or using other order-preserving rayon operations) gives the same sorted order as `iter()`
+	pub fn par_iter(&self) -> impl ParallelIterator<Item = (String, &T)> {
+		self.par_iter_from(Some(0), vec!())
+	}
+	/// Iterates over every key/value pair whose key starts with `prefix` in parallel, where each child subtree of the prefix's node is handled as a separate piece of work
+	pub fn par_iter_prefix(&self, prefix: impl AsRef<str>) -> impl ParallelIterator<Item = (String, &T)> {
+		let prefix = prefix.as_ref();
+		let start_index = self.root_node().step(prefix).map(|node| node.index);
+		self.par_iter_from(start_index, prefix.as_bytes().to_vec())
+	}
+	fn par_iter_from(&self, start_index: Option<u32>, start_path: Vec<u8>) -> impl ParallelIterator<Item = (String, &T)> {
+		let start_value = start_index.and_then(|index| self.node_values.get(index as usize));
+		// SAFETY: nodes with values always have a valid string as their path
+		let start_item = start_value.map(|value| (unsafe { String::from_utf8_unchecked(start_path.clone()) }, value));
+		let children = start_index.map(|index| self.sorted_children(index)).unwrap_or_default();
+		// the child subtrees are disjoint, so they can be walked independently
+		let child_items = children.into_vec().into_par_iter().flat_map_iter(move |(byte, child_index)| {
+			let mut child_path = start_path.clone();
+			child_path.push(byte);
+			self.iter_from(Some(child_index), child_path)
+		});
+		start_item.into_par_iter().chain(child_items)
+	}
+	
+}
+
+impl<T: Send> StringTree<T> {
+	
+	/// Iterates over every value in the tree as mut in parallel (in no particular order)
+	pub fn par_values_mut(&mut self) -> impl ParallelIterator<Item = &mut T> {
+		// every value can be changed, so every node gets the current version
+		let version = self.current_version();
+		self.node_versions.fill(version);
+		self.check_invariants();
+		self.node_values.par_iter_mut()
+	}
+	
+	/// Inserts every key/value pair from a parallel iterator, where the pairs are grouped by first byte and each group is built into a separate tree in parallel
+	/// 
+	/// The new trees are then moved into this tree one at a time. If this tree doesn't have a subtree for a tree's first byte yet, the tree's nodes are appended as one block (with their indices shifted), so this part costs about as much as copying the nodes. Otherwise, the tree is merged in with `extend_from()`. If a key is given multiple times, which value is kept is unspecified.
+	pub fn par_extend<S: AsRef<str> + Send, I: IntoParallelIterator<Item = (S, T)>>(&mut self, source: I) {
+		let groups = source.into_par_iter()
+			.fold(Self::empty_groups, |mut groups, (key, value)| {
+				let group_index = key.as_ref().as_bytes().first().copied().unwrap_or(0) as usize;
+				groups[group_index].push((key, value));
+				groups
+			})
+			.reduce(Self::empty_groups, |mut groups, other_groups| {
+				for (group, other_group) in groups.iter_mut().zip(other_groups) {
+					group.extend(other_group);
+				}
+				groups
+			});
+		let trees = groups.into_par_iter()
+			.filter(|group| !group.is_empty())
+			.map(Self::from)
+			.collect::<Vec<_>>();
+		for tree in trees {
+			if tree.node_pointers[0].iter().any(|(byte, _)| self.child_index(0, *byte).is_some()) {
+				self.extend_from(tree);
+			} else {
+				self.append_tree(tree);
+			}
+		}
+		self.check_invariants();
+	}
+	/// Moves every node of `other` (except its root node) to the end of this tree's node vecs, and adds the children of its root node to this tree's root node
+	/// 
+	/// Note: this tree's root node must not already have a child for any of the bytes of `other`'s root node
+	fn append_tree(&mut self, mut other: Self) {
+		// other's node `i` becomes node `i + offset` here, except for its root node which becomes this tree's root node
+		let offset = self.node_pointers.len() as u32 - 1;
+		let new_index = |index: u32| if index == 0 {0} else {index + offset};
+		let root_children = mem::take(&mut other.node_pointers[0]);
+		let root_value = other.node_values.take(0);
+		let version = self.current_version();
+		self.reserve_nodes(other.node_pointers.len() - 1);
+		for mut pointers in other.node_pointers.drain(1..) {
+			for (_, child_index) in &mut pointers {
+				*child_index = new_index(*child_index);
+			}
+			self.node_pointers.push(pointers);
+		}
+		self.node_fill_counts.extend_from_slice(&other.node_fill_counts[1..]);
+		self.node_stubs.extend_from_slice(&other.node_stubs[1..]);
+		self.node_parents.extend(other.node_parents[1..].iter().map(|&(parent, byte)| (new_index(parent), byte)));
+		for index in 1..other.node_values.len() {
+			self.node_values.push(other.node_values.take(index));
+		}
+		self.node_versions.resize(self.node_pointers.len(), version);
+		// generations are never shortened, so slots that were used before keep theirs
+		if self.node_generations.len() < self.node_pointers.len() {
+			self.node_generations.resize(self.node_pointers.len(), 0);
+		}
+		self.free_nodes.extend(other.free_nodes.iter().map(|&index| new_index(index)));
+		self.touch(0);
+		for (byte, child_index) in root_children {
+			self.node_pointers[0].push((byte, new_index(child_index)));
+			self.node_fill_counts[0] = self.node_fill_counts[0].wrapping_add(1);
+		}
+		if let Some(value) = root_value {
+			self.replace_value(0, Some(value));
+		}
+	}
+	fn empty_groups<S>() -> Vec<Vec<(S, T)>> {
+		(0..256).map(|_| vec!()).collect()
+	}
+	
+	/// Creates a new StringTree from a parallel iterator of key/value pairs, see `par_extend()`
+	pub fn par_from<S: AsRef<str> + Send, I: IntoParallelIterator<Item = (S, T)>>(source: I) -> Self {
+		let mut output = Self::new();
+		output.par_extend(source);
+		output
+	}
+	
+}
@@ -0,0 +1,285 @@
+use crate::*;
+use std::{mem, sync::Arc};
+use smallvec::SmallVec;
+
+
+
+/// An immutable version of StringTree where every edit returns a new version of the tree, and unchanged nodes are shared between versions
+/// 
+/// Cloning this is O(1), and `insert()` and `remove()` only copy the nodes along the path to the edited key, so old versions can be kept around as cheap snapshots
+pub struct PersistentStringTree<T> {
+	pub(crate) root: Arc<PersistentNode<T>>,
+	pub(crate) len: usize,
+}
+
+pub(crate) struct PersistentNode<T> {
+	pub(crate) children: SmallVec<[(u8, Arc<PersistentNode<T>>); 4]>, // sorted by byte
+	pub(crate) value: Option<Arc<T>>,
+}
+
+impl<T> PersistentNode<T> {
+	fn empty() -> Self {
+		Self {
+			children: SmallVec::new(),
+			value: None,
+		}
+	}
+	fn child(&self, byte: u8) -> Option<&Arc<PersistentNode<T>>> {
+		let position = self.children.binary_search_by_key(&byte, |(child_byte, _)| *child_byte).ok()?;
+		Some(&self.children[position].1)
+	}
+	/// Returns a copy of this node's data (the children and value are shared)
+	fn shallow_clone(&self) -> Self {
+		Self {
+			children: self.children.clone(),
+			value: self.value.clone(),
+		}
+	}
+}
+
+impl<T> Drop for PersistentNode<T> {
+	fn drop(&mut self) {
+		// nodes that aren't shared with another version are taken apart one at a time, since dropping them recursively could overflow the stack for long keys
+		let mut stack = mem::take(&mut self.children).into_iter().map(|(_, child)| child).collect::<Vec<_>>();
+		while let Some(child) = stack.pop() {
+			if let Some(mut child) = Arc::into_inner(child) {
+				stack.extend(mem::take(&mut child.children).into_iter().map(|(_, child)| child));
+			}
+		}
+	}
+}
+
+impl<T> PersistentStringTree<T> {
+	
+	/// Creates a new, empty PersistentStringTree
+	pub fn new() -> Self {
+		Self {
+			root: Arc::new(PersistentNode::empty()),
+			len: 0,
+		}
+	}
+	/// Creates a new PersistentStringTree with a given list of key/value pairs
+	pub fn from<S: AsRef<str>, I: IntoIterator<Item = (S, T)>>(source: I) -> Self {
+		let mut output = Self::new();
+		for (key, value) in source {
+			output = output.insert(key, value);
+		}
+		output
+	}
+	
+	/// Returns a new version of the tree with the key/value pair inserted (this version is left unchanged)
+	pub fn insert(&self, key: impl AsRef<str>, value: T) -> Self {
+		let key = key.as_ref().as_bytes();
+		// copy every node along the path to the key (or create it if it doesn't exist yet)
+		let mut path_nodes = Vec::with_capacity(key.len());
+		let mut curr_node = Some(&*self.root);
+		for &byte in key {
+			path_nodes.push(curr_node.map_or_else(PersistentNode::empty, PersistentNode::shallow_clone));
+			curr_node = curr_node.and_then(|node| node.child(byte)).map(|child| &**child);
+		}
+		let mut new_node = curr_node.map_or_else(PersistentNode::empty, PersistentNode::shallow_clone);
+		let previous = new_node.value.replace(Arc::new(value));
+		// then link the copies together from the bottom up
+		for (mut node, &byte) in path_nodes.into_iter().zip(key).rev() {
+			match node.children.binary_search_by_key(&byte, |(child_byte, _)| *child_byte) {
+				Ok(position) => node.children[position].1 = Arc::new(new_node),
+				Err(position) => node.children.insert(position, (byte, Arc::new(new_node))),
+			}
+			new_node = node;
+		}
+		Self {
+			root: Arc::new(new_node),
+			len: if previous.is_some() {self.len} else {self.len + 1},
+		}
+	}
+	
+	/// Returns a new version of the tree with the key removed (this version is left unchanged), or None if the key isn't in the tree
+	pub fn remove(&self, key: impl AsRef<str>) -> Option<Self> {
+		// find the path to the key first, so that nothing is copied if the key isn't in the tree
+		let mut path_nodes = vec!();
+		let mut curr_node = &*self.root;
+		for byte in key.as_ref().bytes() {
+			let position = curr_node.children.binary_search_by_key(&byte, |(child_byte, _)| *child_byte).ok()?;
+			path_nodes.push((curr_node, position));
+			curr_node = &curr_node.children[position].1;
+		}
+		curr_node.value.as_ref()?;
+		// then copy the nodes along the path from the bottom up, where None means that the node should be removed
+		let mut new_node = if curr_node.children.is_empty() {None} else {
+			let mut output = curr_node.shallow_clone();
+			output.value = None;
+			Some(output)
+		};
+		for (node, position) in path_nodes.into_iter().rev() {
+			let mut output = node.shallow_clone();
+			match new_node {
+				Some(new_node) => output.children[position].1 = Arc::new(new_node),
+				None => {output.children.remove(position);}
+			}
+			new_node = if output.children.is_empty() && output.value.is_none() {None} else {Some(output)};
+		}
+		Some(Self {
+			root: Arc::new(new_node.unwrap_or_else(PersistentNode::empty)),
+			len: self.len - 1,
+		})
+	}
+	
+	/// Gets a value from a given key (or None)
+	pub fn get(&self, key: impl AsRef<str>) -> Option<&T> {
+		self.root_node().get(key)
+	}
+	/// Returns true if the key is in the tree
+	pub fn contains(&self, key: impl AsRef<str>) -> bool {
+		self.get(key).is_some()
+	}
+	/// Steps further into the tree and returns a node reference (or None)
+	pub fn step<'a>(&'a self, key: impl AsRef<str>) -> Option<PersistentStringTreeNode<'a, T>> {
+		self.root_node().step(key)
+	}
+	/// Iterates over every key/value pair in the tree, sorted by key bytes
+	pub fn iter(&self) -> impl Iterator<Item = (String, &T)> {
+		self.root_node().iter()
+	}
+	/// Iterates over every key/value pair whose key starts with `prefix`, sorted by key bytes
+	pub fn iter_prefix(&self, prefix: impl AsRef<str>) -> impl Iterator<Item = (String, &T)> {
+		let node = self.step(prefix);
+		node.into_iter().flat_map(|node| node.iter())
+	}
+	
+	/// Returns the number of keys in the tree
+	pub const fn len(&self) -> usize {
+		self.len
+	}
+	/// Returns true if there are no keys in the tree
+	pub const fn is_empty(&self) -> bool {
+		self.len == 0
+	}
+	/// Returns true if both trees are the same version (or are clones of the same version)
+	pub fn ptr_eq(&self, other: &Self) -> bool {
+		Arc::ptr_eq(&self.root, &other.root)
+	}
+	
+	pub fn root_node<'a>(&'a self) -> PersistentStringTreeNode<'a, T> {
+		PersistentStringTreeNode {
+			node: &self.root,
+			path: vec!(),
+		}
+	}
+	
+}
+
+impl<T> Clone for PersistentStringTree<T> {
+	fn clone(&self) -> Self {
+		Self {
+			root: self.root.clone(),
+			len: self.len,
+		}
+	}
+}
+
+impl<T> Default for PersistentStringTree<T> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+
+
+/// A reference to a node within a PersistentStringTree
+/// 
+/// Because nodes are shared between versions they don't know their parents, so this stores the path that was used to reach it
+pub struct PersistentStringTreeNode<'a, T> {
+	pub(crate) node: &'a PersistentNode<T>,
+	pub(crate) path: Vec<u8>,
+}
+
+impl<'a, T> PersistentStringTreeNode<'a, T> {
+	
+	/// Steps further into the tree and returns the value at the desired position (or None)
+	pub fn get(&self, key: impl AsRef<str>) -> Option<&'a T> {
+		self.get_node_of_key(key.as_ref().as_bytes())?.value.as_deref()
+	}
+	/// Steps further into the tree and returns a new node reference (or None)
+	pub fn step(&self, key: impl AsRef<str>) -> Option<PersistentStringTreeNode<'a, T>> {
+		let key = key.as_ref().as_bytes();
+		let node = self.get_node_of_key(key)?;
+		let mut path = self.path.clone();
+		path.extend_from_slice(key);
+		Some(Self {
+			node,
+			path,
+		})
+	}
+	
+	fn get_node_of_key(&self, key: &[u8]) -> Option<&'a PersistentNode<T>> {
+		let mut curr_node = self.node;
+		for curr_byte in key {
+			curr_node = curr_node.child(*curr_byte)?;
+		}
+		Some(curr_node)
+	}
+	
+	/// Returns the value at this node (or None)
+	pub fn value(&self) -> Option<&'a T> {
+		self.node.value.as_deref()
+	}
+	
+	/// Creates and returns the string that is needed to reach this node from the root node
+	pub fn path(&self) -> String {
+		// SAFETY: this is only ever extended by whole `str`s or whole chars, so it should be a valid string
+		unsafe { String::from_utf8_unchecked(self.path.clone()) }
+	}
+	
+	/// Iterates over the children of this node.
+	/// 
+	/// Note: for multi-byte characters, this does traverse deeper into the tree to ensure that the resulting node will have a valid `path()`
+	pub fn children(&self) -> impl Iterator<Item = PersistentStringTreeNode<'a, T>> + use<'a, T> {
+		let start_node = self.node;
+		let start_path = self.path.clone();
+		IterableCoroutine(#[coroutine] move || {
+			// (node, path, remaining bytes until the char is complete)
+			let mut stack = vec!();
+			for (byte, child) in start_node.children.iter().rev() {
+				let mut child_path = start_path.clone();
+				child_path.push(*byte);
+				// the number of leading 1 bits is the length of the char (or 0 for single-byte chars)
+				stack.push((&**child, child_path, (byte.leading_ones() as usize).saturating_sub(1)));
+			}
+			while let Some((node, path, remaining_bytes)) = stack.pop() {
+				if remaining_bytes == 0 {
+					yield PersistentStringTreeNode {
+						node,
+						path,
+					};
+					continue;
+				}
+				for (byte, child) in node.children.iter().rev() {
+					let mut child_path = path.clone();
+					child_path.push(*byte);
+					stack.push((&**child, child_path, remaining_bytes - 1));
+				}
+			}
+		})
+	}
+	
+	/// Iterates over every key/value pair at or below this node, sorted by key bytes
+	pub fn iter(&self) -> impl Iterator<Item = (String, &'a T)> + use<'a, T> {
+		let start_node = self.node;
+		let start_path = self.path.clone();
+		IterableCoroutine(#[coroutine] move || {
+			let mut stack = vec!((start_node, start_path));
+			while let Some((node, path)) = stack.pop() {
+				for (byte, child) in node.children.iter().rev() {
+					let mut child_path = path.clone();
+					child_path.push(*byte);
+					stack.push((&**child, child_path));
+				}
+				if let Some(value) = node.value.as_deref() {
+					// SAFETY: nodes with values always have a valid string as their path
+					yield (unsafe { String::from_utf8_unchecked(path) }, value);
+				}
+			}
+		})
+	}
+	
+}
@@ -0,0 +1,182 @@
+use crate::*;
+use std::fmt;
+
+
+
+/// A URL router built on a StringTree, which maps route patterns to values
+/// 
+/// Route patterns are made of `/`-separated segments, which can be:
+/// - static segments, like `users`
+/// - parameters, like `:id`, which capture exactly one non-empty segment
+/// - catch-alls, like `*rest`, which capture everything after them and must be the last segment
+/// 
+/// When multiple routes match a path, static segments take priority over parameters, and parameters take priority over catch-alls
+pub struct Router<T> {
+	pub(crate) tree: StringTree<RouteEntry<T>>,
+	pub(crate) len: usize,
+}
+
+pub(crate) struct RouteEntry<T> {
+	pub(crate) pattern: String,
+	pub(crate) param_names: Vec<String>,
+	pub(crate) value: T,
+}
+
+/// The result of matching a path with `Router::at()`
+pub struct RouteMatch<'a, 'p, T> {
+	/// The route pattern that was matched
+	pub pattern: &'a str,
+	/// The value that was registered for the matched route
+	pub value: &'a T,
+	/// The captured parameters (name, value), in the order they appear in the route pattern
+	pub params: Vec<(&'a str, &'p str)>,
+}
+
+impl<'a, 'p, T> RouteMatch<'a, 'p, T> {
+	/// Returns the captured value of the parameter with the given name (or None)
+	pub fn param(&self, name: impl AsRef<str>) -> Option<&'p str> {
+		let name = name.as_ref();
+		self.params.iter().find(|(param_name, _)| *param_name == name).map(|(_, value)| *value)
+	}
+}
+
+/// An error that can occur when registering a route
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RouteError {
+	/// The route matches exactly the same paths as an already registered route
+	Conflict {
+		/// The route that was being registered
+		route: String,
+		/// The already registered route
+		existing: String,
+	},
+	/// The route has a catch-all segment that isn't the last segment
+	CatchAllNotLast(String),
+	/// The route has a parameter or catch-all without a name
+	MissingParamName(String),
+}
+
+impl fmt::Display for RouteError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Conflict {route, existing} => write!(f, "route \"{route}\" conflicts with existing route \"{existing}\""),
+			Self::CatchAllNotLast(route) => write!(f, "route \"{route}\" has a catch-all segment that isn't the last segment"),
+			Self::MissingParamName(route) => write!(f, "route \"{route}\" has a parameter without a name"),
+		}
+	}
+}
+
+impl std::error::Error for RouteError {}
+
+impl<T> Router<T> {
+	
+	/// Creates a new, empty Router
+	pub fn new() -> Self {
+		Self {
+			tree: StringTree::new(),
+			len: 0,
+		}
+	}
+	
+	/// Registers a route pattern with a value, or returns an error if the pattern is invalid or conflicts with an existing route
+	pub fn insert(&mut self, pattern: impl AsRef<str>, value: T) -> Result<(), RouteError> {
+		let pattern = pattern.as_ref();
+		let (key, param_names) = Self::parse_pattern(pattern)?;
+		if let Some(existing) = self.tree.get(&key) {
+			return Err(RouteError::Conflict {
+				route: pattern.to_string(),
+				existing: existing.pattern.clone(),
+			});
+		}
+		self.tree.insert(key, RouteEntry {
+			pattern: pattern.to_string(),
+			param_names,
+			value,
+		});
+		self.len += 1;
+		Ok(())
+	}
+	
+	/// Removes a route pattern and returns its value (or None if the route isn't registered)
+	/// 
+	/// Parameter names are ignored, so `/users/:id` also removes `/users/:name`
+	pub fn remove(&mut self, pattern: impl AsRef<str>) -> Option<T> {
+		let (key, _) = Self::parse_pattern(pattern.as_ref()).ok()?;
+		let output = self.tree.remove(key)?;
+		self.len -= 1;
+		Some(output.value)
+	}
+	
+	/// Returns the number of registered routes
+	pub const fn len(&self) -> usize {
+		self.len
+	}
+	/// Returns true if there are no registered routes
+	pub const fn is_empty(&self) -> bool {
+		self.len == 0
+	}
+	
+	/// Finds the highest priority route that matches the given path, and returns its value and captured parameters (or None)
+	pub fn at<'a, 'p>(&'a self, path: &'p str) -> Option<RouteMatch<'a, 'p, T>> {
+		let mut segments = vec!();
+		let mut offset = 0;
+		for segment in path.split('/') {
+			segments.push((offset, segment));
+			offset += segment.len() + 1;
+		}
+		let mut param_values = vec!();
+		let entry = Self::find(self.tree.segments('/'), path, &segments, &mut param_values)?;
+		Some(RouteMatch {
+			pattern: &entry.pattern,
+			value: &entry.value,
+			params: entry.param_names.iter().map(String::as_str).zip(param_values).collect(),
+		})
+	}
+	
+	fn find<'a, 'p>(view: SegmentView<'a, RouteEntry<T>>, path: &'p str, segments: &[(usize, &'p str)], param_values: &mut Vec<&'p str>) -> Option<&'a RouteEntry<T>> {
+		let Some(&(offset, segment)) = segments.first() else {return view.value();};
+		// static
+		if segment != ":" && segment != "*" && let Some(next_view) = view.step_segment(segment) && let Some(entry) = Self::find(next_view, path, &segments[1..], param_values) {
+			return Some(entry);
+		}
+		// param
+		if !segment.is_empty() && let Some(next_view) = view.step_segment(":") {
+			param_values.push(segment);
+			if let Some(entry) = Self::find(next_view, path, &segments[1..], param_values) {return Some(entry);}
+			param_values.pop();
+		}
+		// catch-all
+		let entry = view.step_segment("*")?.value()?;
+		param_values.push(&path[offset..]);
+		Some(entry)
+	}
+	
+	/// Converts a route pattern into the key that is stored in the tree, where parameters become `:` and catch-alls become `*`
+	fn parse_pattern(pattern: &str) -> Result<(String, Vec<String>), RouteError> {
+		let mut key_segments = vec!();
+		let mut param_names = vec!();
+		let mut segments = pattern.split('/').peekable();
+		while let Some(segment) = segments.next() {
+			if let Some(name) = segment.strip_prefix(':') {
+				if name.is_empty() {return Err(RouteError::MissingParamName(pattern.to_string()));}
+				key_segments.push(":");
+				param_names.push(name.to_string());
+			} else if let Some(name) = segment.strip_prefix('*') {
+				if name.is_empty() {return Err(RouteError::MissingParamName(pattern.to_string()));}
+				if segments.peek().is_some() {return Err(RouteError::CatchAllNotLast(pattern.to_string()));}
+				key_segments.push("*");
+				param_names.push(name.to_string());
+			} else {
+				key_segments.push(segment);
+			}
+		}
+		Ok((key_segments.join("/"), param_names))
+	}
+	
+}
+
+impl<T> Default for Router<T> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
@@ -0,0 +1,170 @@
+use crate::*;
+use smallvec::SmallVec;
+
+
+
+/// A view of a StringTree node that traverses the tree in whole segments (like `usr` in `usr/bin/env`) instead of chars
+/// 
+/// A view is positioned either at the root or at the end of a segment, so stepping from `usr` with the segment `bin` steps over `/bin`
+/// 
+/// Every separator belongs to the segment after it, so a path that ends with the separator (like `/a/`) is at the end of an empty segment, one segment deeper than `/a`
+pub struct SegmentView<'a, T> {
+	pub(crate) node: StringTreeNode<'a, T>,
+	pub(crate) separator: char,
+	pub(crate) depth: u32,
+}
+
+impl<T> StringTree<T> {
+	/// Returns a segment view of the root node, using the given separator
+	pub const fn segments<'a>(&'a self, separator: char) -> SegmentView<'a, T> {
+		SegmentView {
+			node: self.root_node(),
+			separator,
+			depth: 0,
+		}
+	}
+}
+
+impl<'a, T> StringTreeNode<'a, T> {
+	/// Returns a segment view of this node, using the given separator
+	/// 
+	/// The segment depth is the number of separators in this node's path plus one (or zero for the root node), which counts a trailing separator as the start of a new segment
+	pub fn segment_view(&self, separator: char) -> SegmentView<'a, T> {
+		let depth = if self.index == 0 {0} else {self.path().matches(separator).count() as u32 + 1};
+		SegmentView {
+			node: StringTreeNode {
+				ref_tree: self.ref_tree,
+				index: self.index,
+			},
+			separator,
+			depth,
+		}
+	}
+}
+
+impl<'a, T> SegmentView<'a, T> {
+	
+	/// Returns the node that this view is positioned at
+	pub const fn node(&self) -> StringTreeNode<'a, T> {
+		StringTreeNode {
+			ref_tree: self.node.ref_tree,
+			index: self.node.index,
+		}
+	}
+	/// Returns the value at this view's node (or None)
+	pub fn value(&self) -> Option<&'a T> {
+		self.node.value()
+	}
+	/// Returns the separator used by this view
+	pub const fn separator(&self) -> char {
+		self.separator
+	}
+	/// Returns the number of segments needed to reach this view's node from the root node
+	pub const fn segment_depth(&self) -> u32 {
+		self.depth
+	}
+	/// Creates and returns the string that is needed to reach this view's node from the root node
+	pub fn path(&self) -> String {
+		self.node.path()
+	}
+	
+	/// Steps over one segment (plus the separator before it if this view isn't at the root) and returns the new view (or None)
+	/// 
+	/// Note: `segment` should not contain the separator, use `step_segments()` for multiple segments
+	pub fn step_segment(&self, segment: impl AsRef<str>) -> Option<SegmentView<'a, T>> {
+		let mut node = self.node();
+		if self.depth > 0 {
+			let mut separator_bytes = [0; 4];
+			node = node.step(self.separator.encode_utf8(&mut separator_bytes))?;
+		}
+		let node = node.step(segment)?;
+		Some(SegmentView {
+			node,
+			separator: self.separator,
+			depth: self.depth + 1,
+		})
+	}
+	/// Steps over every separator-delimited segment in `segments` and returns the new view (or None)
+	pub fn step_segments(&self, segments: impl AsRef<str>) -> Option<SegmentView<'a, T>> {
+		let mut output = SegmentView {
+			node: self.node(),
+			separator: self.separator,
+			depth: self.depth,
+		};
+		for segment in segments.as_ref().split(self.separator) {
+			output = output.step_segment(segment)?;
+		}
+		Some(output)
+	}
+	
+	/// Returns the view of the previous segment boundary (or None if this view is at the root)
+	pub fn parent_segment(&self) -> Option<SegmentView<'a, T>> {
+		if self.depth == 0 {return None;}
+		let tree = self.node.ref_tree;
+		let path = self.node.path();
+		// step back over the last segment along with the separator before it (which is the whole path in the first segment)
+		let steps_up = path.len() - path.rfind(self.separator).unwrap_or(0);
+		let mut index = self.node.index;
+		for _ in 0..steps_up {
+			index = tree.node_parents[index as usize].0;
+		}
+		Some(SegmentView {
+			node: StringTreeNode {
+				ref_tree: tree,
+				index,
+			},
+			separator: self.separator,
+			depth: self.depth - 1,
+		})
+	}
+	
+	/// Iterates over the segments directly below this view in sorted byte order, yielding each segment's name and view
+	/// 
+	/// A segment exists if its node has a value or if the separator directly follows it
+	pub fn child_segments(&self) -> impl Iterator<Item = (String, SegmentView<'a, T>)> {
+		let tree = self.node.ref_tree;
+		let separator = self.separator;
+		let depth = self.depth + 1;
+		let mut separator_bytes = [0; 4];
+		let separator_bytes: SmallVec<[u8; 4]> = separator.encode_utf8(&mut separator_bytes).as_bytes().into();
+		let start_index = if self.depth == 0 {
+			Some(self.node.index)
+		} else {
+			self.node.step(separator.encode_utf8(&mut [0; 4])).map(|node| node.index)
+		};
+		IterableCoroutine(#[coroutine] move || {
+			let Some(start_index) = start_index else {return;};
+			let mut stack = vec!((start_index, vec!()));
+			while let Some((index, segment_bytes)) = stack.pop() {
+				let has_separator_child = {
+					let mut curr_index = Some(index);
+					for &byte in &separator_bytes {
+						curr_index = curr_index.and_then(|curr_index| tree.child_index(curr_index, byte));
+					}
+					curr_index.is_some()
+				};
+				let mut children = tree.node_pointers[index as usize].clone();
+				children.sort_unstable_by_key(|(byte, _)| *byte);
+				for &(byte, child_index) in children.iter().rev() {
+					let mut child_segment_bytes = segment_bytes.clone();
+					child_segment_bytes.push(byte);
+					if child_segment_bytes.ends_with(&separator_bytes) {continue;}
+					stack.push((child_index, child_segment_bytes));
+				}
+				if tree.node_values.is_some(index as usize) || has_separator_child {
+					// SAFETY: nodes with values or separators after them are always at char boundaries, so this should be a valid string
+					let segment = unsafe { String::from_utf8_unchecked(segment_bytes) };
+					yield (segment, SegmentView {
+						node: StringTreeNode {
+							ref_tree: tree,
+							index,
+						},
+						separator,
+						depth,
+					});
+				}
+			}
+		})
+	}
+	
+}
@@ -0,0 +1,438 @@
+use crate::*;
+use smallvec::SmallVec;
+use std::{fmt, mem, sync::atomic::{AtomicU64, Ordering}};
+
+
+
+/// A [trie](https://en.wikipedia.org/wiki/Trie) that maps sequences of symbols (like token ids) to values, with the same layout and traversal API as StringTree
+/// 
+/// StringTree is the `u8` version of this, so the public sequence API here is only available for symbol types that implement `Symbol` (which `u8` doesn't, use StringTree or ByteStringTree instead).
+/// 
+/// To traverse this tree node-by-node, you must call `SequenceTree::root_node()`.
+pub struct SequenceTree<S, T> {
+	pub(crate) node_pointers: Vec<SmallVec<[(S, u32); 4]>>, // this could technically use a smaller u32 array but the way that it is right now probably eliminates bounds checking
+	pub(crate) node_fill_counts: Vec<u8>, // this technically can overflow, but even if it does, nothing bad happens (because nodes are only removed based on their children lists, and for bytes it can never overflow to anything above 0 because 256 is the maximum)
+	pub(crate) node_stubs: Vec<[u8; 16]>,
+	pub(crate) node_parents: Vec<(u32, S)>, // (parent index, index within parent)
+	pub(crate) node_values: NodeValues<T>, // stores zero-sized values as one bit per node
+	pub(crate) node_generations: Vec<u32>, // incremented whenever a slot stops holding its node, so that old NodeIds for it become invalid (this is never shortened, so it can be longer than the other vecs)
+	pub(crate) free_nodes: Vec<u32>, // slots of removed nodes, which are reused before new slots are added
+	pub(crate) node_versions: Vec<u64>, // changes whenever anything in the node's subtree changes (see `touch()`), so equal versions in a tree and its clone mean equal subtrees (which is why every value write has to go through `replace_value()` or touch the node first)
+	pub(crate) version: u64, // the version that `touch()` gives to changed nodes, which is replaced once a clone could have it too (see `current_version()`)
+}
+
+static NEXT_VERSION: AtomicU64 = AtomicU64::new(1);
+/// Every version up to this one might be in more than one tree, because it was handed out before a tree was cloned
+static SHARED_VERSIONS: AtomicU64 = AtomicU64::new(0);
+
+/// Returns a version that no tree has used before
+fn new_version() -> u64 {
+	NEXT_VERSION.fetch_add(1, Ordering::Relaxed)
+}
+
+/// A symbol type that can be used for the keys of a SequenceTree
+/// 
+/// This is implemented for the integer types (other than `u8`), `bool` and `char`, and can be implemented for custom symbols. The `Default` value is only used as a placeholder for the root node, and `Debug` is used to show the symbols in an IntegrityError.
+pub trait Symbol: Copy + Ord + Default + fmt::Debug {}
+
+impl Symbol for u16 {}
+impl Symbol for u32 {}
+impl Symbol for u64 {}
+impl Symbol for u128 {}
+impl Symbol for usize {}
+impl Symbol for i8 {}
+impl Symbol for i16 {}
+impl Symbol for i32 {}
+impl Symbol for i64 {}
+impl Symbol for i128 {}
+impl Symbol for isize {}
+impl Symbol for bool {}
+impl Symbol for char {}
+
+impl<S: Copy + Ord + Default, T> SequenceTree<S, T> {
+	
+	/// Creates a new, empty tree
+	pub fn new() -> Self {
+		let version = new_version();
+		let mut node_values = NodeValues::new();
+		node_values.push(None);
+		Self {
+			node_pointers: vec!(SmallVec::new()),
+			node_fill_counts: vec!(0),
+			node_stubs: vec!([0; 16]),
+			node_parents: vec!((0, S::default())),
+			node_values,
+			node_generations: vec!(0),
+			free_nodes: vec!(),
+			node_versions: vec!(version),
+			version,
+		}
+	}
+	/// Creates a new, empty tree with space for at least `nodes` nodes (including the root node)
+	pub fn with_capacity(nodes: usize) -> Self {
+		let mut output = Self::new();
+		output.reserve(nodes.saturating_sub(1));
+		output
+	}
+	/// Reserves space for at least `additional` more nodes
+	pub fn reserve(&mut self, additional: usize) {
+		self.reserve_nodes(additional);
+	}
+	/// Releases as much unused memory as possible, including the unused space of children lists that had to be moved to the heap
+	pub fn shrink_to_fit(&mut self) {
+		for pointers in &mut self.node_pointers {
+			pointers.shrink_to_fit();
+		}
+		self.node_pointers.shrink_to_fit();
+		self.node_fill_counts.shrink_to_fit();
+		self.node_stubs.shrink_to_fit();
+		self.node_parents.shrink_to_fit();
+		self.node_values.shrink_to_fit();
+		self.node_generations.shrink_to_fit();
+		self.free_nodes.shrink_to_fit();
+		self.node_versions.shrink_to_fit();
+	}
+	/// Returns the number of bytes that this tree has allocated, split into each part of the tree
+	/// 
+	/// Note: this doesn't include any memory that the values themselves have allocated
+	pub fn memory_usage(&self) -> MemoryReport {
+		let spilled_pointers_bytes = self.node_pointers.iter()
+			.filter(|pointers| pointers.spilled())
+			.map(|pointers| pointers.capacity() * mem::size_of::<(S, u32)>())
+			.sum::<usize>();
+		MemoryReport {
+			nodes: self.node_pointers.len() - self.free_nodes.len(),
+			pointers: self.node_pointers.capacity() * mem::size_of::<SmallVec<[(S, u32); 4]>>() + spilled_pointers_bytes,
+			fill_counts: self.node_fill_counts.capacity() * mem::size_of::<u8>(),
+			stubs: self.node_stubs.capacity() * mem::size_of::<[u8; 16]>(),
+			parents: self.node_parents.capacity() * mem::size_of::<(u32, S)>(),
+			values: self.node_values.allocated_bytes(),
+			generations: self.node_generations.capacity() * mem::size_of::<u32>() + self.free_nodes.capacity() * mem::size_of::<u32>(),
+			versions: self.node_versions.capacity() * mem::size_of::<u64>(),
+		}
+	}
+	
+	/// Returns the index of the child of `node` that is reached with `symbol` (or None)
+	pub(crate) fn child_index(&self, node: u32, symbol: S) -> Option<u32> {
+		self.node_pointers[node as usize].iter().find(|(child_symbol, _)| *child_symbol == symbol).map(|(_, child_index)| *child_index)
+	}
+	/// Returns the index of the node that is reached by following `key` from `node` (or None)
+	pub(crate) fn descend(&self, node: u32, key: &[S]) -> Option<u32> {
+		let mut curr_index = node;
+		for &symbol in key {
+			curr_index = self.child_index(curr_index, symbol)?;
+		}
+		Some(curr_index)
+	}
+	/// Returns the symbols that are needed to reach `node` from the root node
+	pub(crate) fn path_of(&self, node: u32) -> Vec<S> {
+		let mut output = vec!();
+		let mut i = node as usize;
+		while i != 0 {
+			let (parent_index, index_within_parent) = self.node_parents[i];
+			output.push(index_within_parent);
+			i = parent_index as usize;
+		}
+		output.reverse();
+		output
+	}
+	/// Returns the (symbol, index) pairs of the children of `node`, sorted by symbol
+	pub(crate) fn sorted_children(&self, node: u32) -> SmallVec<[(S, u32); 4]> {
+		let mut output = self.node_pointers[node as usize].clone();
+		output.sort_unstable_by_key(|(symbol, _)| *symbol);
+		output
+	}
+	/// Reserves space for at least `additional` more nodes in every per-node list
+	pub(crate) fn reserve_nodes(&mut self, additional: usize) {
+		self.node_pointers.reserve(additional);
+		self.node_fill_counts.reserve(additional);
+		self.node_stubs.reserve(additional);
+		self.node_parents.reserve(additional);
+		self.node_values.reserve(additional);
+		self.node_generations.reserve(additional);
+		self.node_versions.reserve(additional);
+	}
+	/// Adds a new leaf node as the child of `parent` that is reached with `symbol`, and returns its index
+	/// 
+	/// This reuses the slot of a removed node if there is one, otherwise the node is appended
+	/// 
+	/// Note: `parent` must not already have a child for `symbol`
+	pub(crate) fn push_node(&mut self, parent: u32, symbol: S, value: Option<T>) -> u32 {
+		self.touch(parent);
+		let version = self.current_version();
+		let index = if let Some(index) = self.free_nodes.pop() {
+			self.node_fill_counts[index as usize] = 0;
+			self.node_stubs[index as usize] = [0; 16];
+			self.node_parents[index as usize] = (parent, symbol);
+			self.node_values.replace(index as usize, value);
+			self.node_versions[index as usize] = version;
+			index
+		} else {
+			let index = self.node_pointers.len() as u32;
+			self.node_pointers.push(SmallVec::new());
+			self.node_fill_counts.push(0);
+			self.node_stubs.push([0; 16]);
+			self.node_parents.push((parent, symbol));
+			self.node_values.push(value);
+			self.node_versions.push(version);
+			if self.node_generations.len() <= index as usize {
+				self.node_generations.push(0);
+			}
+			index
+		};
+		self.node_pointers[parent as usize].push((symbol, index));
+		self.node_fill_counts[parent as usize] = self.node_fill_counts[parent as usize].wrapping_add(1);
+		index
+	}
+	/// Removes a node (which must be a leaf and not the root node) and returns its value
+	/// 
+	/// The node's slot is either dropped (if it is the last slot) or added to the list of free slots, and its generation is incremented either way
+	pub(crate) fn free_node(&mut self, index: u32) -> Option<T> {
+		let (parent, symbol) = self.node_parents[index as usize];
+		self.touch(parent);
+		self.node_pointers[parent as usize].retain(|(child_symbol, _)| *child_symbol != symbol);
+		self.node_fill_counts[parent as usize] = self.node_fill_counts[parent as usize].wrapping_sub(1);
+		self.node_generations[index as usize] = self.node_generations[index as usize].wrapping_add(1);
+		if index as usize == self.node_pointers.len() - 1 {
+			self.node_pointers.pop();
+			self.node_fill_counts.pop();
+			self.node_stubs.pop();
+			self.node_parents.pop();
+			self.node_versions.pop();
+			self.node_values.pop()
+		} else {
+			self.free_nodes.push(index);
+			self.node_pointers[index as usize] = SmallVec::new();
+			self.node_parents[index as usize] = (0, S::default());
+			self.node_values.take(index as usize)
+		}
+	}
+	/// Gives `index` and all of its ancestors the current version, which must be done whenever anything in a node's subtree changes (including when its value is borrowed as mut)
+	/// 
+	/// Any node with the current version already has it for all of its ancestors too, so this stops early and costs O(1) amortized for a series of changes
+	pub(crate) fn touch(&mut self, index: u32) {
+		let version = self.current_version();
+		let mut i = index as usize;
+		while self.node_versions[i] != version {
+			self.node_versions[i] = version;
+			if i == 0 {break;}
+			i = self.node_parents[i].0 as usize;
+		}
+	}
+	/// Returns the version that changed nodes get, which is replaced with a new one if any tree has been cloned since it was handed out
+	/// 
+	/// This way cloning doesn't have to change the tree that is cloned, and a tree's changes after a clone can't get a version that the clone also has
+	pub(crate) fn current_version(&mut self) -> u64 {
+		if self.version <= SHARED_VERSIONS.load(Ordering::Relaxed) {
+			self.version = new_version();
+		}
+		self.version
+	}
+	/// Sets the value of `index` and returns the previous value if it exists, see `touch()`
+	pub(crate) fn replace_value(&mut self, index: u32, value: Option<T>) -> Option<T> {
+		self.touch(index);
+		self.node_values.replace(index as usize, value)
+	}
+	pub(crate) fn is_id_valid(&self, id: NodeId) -> bool {
+		// freed slots always have a newer generation than any id that was created for them, so free slots don't need to be checked separately
+		(id.index as usize) < self.node_pointers.len() && self.node_generations[id.index as usize] == id.generation
+	}
+	pub(crate) fn id_of(&self, index: u32) -> NodeId {
+		NodeId {
+			index,
+			generation: self.node_generations[index as usize],
+		}
+	}
+	
+	/// Follows `key` from `node` (adding any missing nodes), sets the value there, and returns the previous value if it exists
+	pub(crate) fn set_at(&mut self, node: u32, key: &[S], value: T) -> Option<T> {
+		let mut curr_index = node;
+		for &symbol in key {
+			curr_index = match self.child_index(curr_index, symbol) {
+				Some(next_index) => next_index,
+				None => self.push_node(curr_index, symbol, None),
+			};
+		}
+		self.replace_value(curr_index, Some(value))
+	}
+	/// Follows `key` from `node`, takes the value there, and removes any nodes that are no longer needed
+	pub(crate) fn remove_at(&mut self, node: u32, key: &[S]) -> Option<T> {
+		let index = self.descend(node, key)?;
+		let output = self.replace_value(index, None);
+		let mut end_node = index;
+		while end_node != 0 && self.node_values.is_none(end_node as usize) && self.node_pointers[end_node as usize].is_empty() {
+			let parent_index = self.node_parents[end_node as usize].0;
+			self.free_node(end_node);
+			end_node = parent_index;
+		}
+		output
+	}
+	pub(crate) fn iter_sequences_from(&self, start_index: Option<u32>, start_path: Vec<S>) -> impl Iterator<Item = (Vec<S>, &T)> {
+		IterableCoroutine(#[coroutine] move || {
+			let Some(start_index) = start_index else {return;};
+			let mut stack = vec!((start_index, start_path));
+			while let Some((index, path)) = stack.pop() {
+				for (symbol, child_index) in self.sorted_children(index).into_iter().rev() {
+					let mut child_path = path.clone();
+					child_path.push(symbol);
+					stack.push((child_index, child_path));
+				}
+				if let Some(value) = self.node_values.get(index as usize) {
+					yield (path, value);
+				}
+			}
+		})
+	}
+	
+}
+
+impl<S: Symbol, T> SequenceTree<S, T> {
+	
+	/// Creates a new SequenceTree with a given list of key/value pairs
+	pub fn from<K: AsRef<[S]>, I: IntoIterator<Item = (K, T)>>(source: I) -> Self {
+		let mut output = Self::new();
+		for (key, value) in source {
+			output.insert(key, value);
+		}
+		output
+	}
+	
+	/// Inserts a key/value pair into the tree, and returns the previous value if it exists
+	pub fn insert(&mut self, key: impl AsRef<[S]>, value: T) -> Option<T> {
+		let output = self.set_at(0, key.as_ref(), value);
+		self.check_invariants();
+		output
+	}
+	/// Gets a value from a given key (or None)
+	pub fn get(&self, key: impl AsRef<[S]>) -> Option<&T> {
+		let index = self.descend(0, key.as_ref())?;
+		self.node_values.get(index as usize)
+	}
+	/// Gets a value from a given key (or None)
+	pub fn get_mut(&mut self, key: impl AsRef<[S]>) -> Option<&mut T> {
+		let index = self.descend(0, key.as_ref())?;
+		self.touch(index);
+		self.check_invariants();
+		self.node_values.get_mut(index as usize)
+	}
+	/// Returns true if the key has a value
+	pub fn contains_key(&self, key: impl AsRef<[S]>) -> bool {
+		self.get(key).is_some()
+	}
+	/// Removes and returns a value from a given key (or None if there was no value at the given key)
+	/// 
+	/// This also removes any unneeded nodes, and the NodeIds of all other nodes stay valid
+	pub fn remove(&mut self, key: impl AsRef<[S]>) -> Option<T> {
+		let output = self.remove_at(0, key.as_ref());
+		self.check_invariants();
+		output
+	}
+	
+	/// Iterates over every key/value pair in the tree, sorted by key
+	pub fn iter(&self) -> impl Iterator<Item = (Vec<S>, &T)> {
+		self.iter_sequences_from(Some(0), vec!())
+	}
+	/// Iterates over every key/value pair whose key starts with `prefix`, sorted by key
+	pub fn iter_prefix(&self, prefix: impl AsRef<[S]>) -> impl Iterator<Item = (Vec<S>, &T)> {
+		let prefix = prefix.as_ref();
+		self.iter_sequences_from(self.descend(0, prefix), prefix.to_vec())
+	}
+	
+	/// Steps further into the tree and returns a node reference (or None)
+	pub fn step<'a>(&'a self, key: impl AsRef<[S]>) -> Option<SequenceTreeNode<'a, S, T>> {
+		self.root_node().step(key)
+	}
+	pub const fn root_node<'a>(&'a self) -> SequenceTreeNode<'a, S, T> {
+		SequenceTreeNode {
+			ref_tree: self,
+			index: 0,
+		}
+	}
+	/// Returns a reference to the node with the given id (or None if that node has been removed)
+	pub fn node<'a>(&'a self, id: NodeId) -> Option<SequenceTreeNode<'a, S, T>> {
+		if !self.is_id_valid(id) {return None;}
+		Some(SequenceTreeNode {
+			ref_tree: self,
+			index: id.index,
+		})
+	}
+	
+}
+
+impl<S: Copy + Ord + Default, T> Default for SequenceTree<S, T> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<S: Clone, T: Clone> Clone for SequenceTree<S, T> {
+	fn clone(&self) -> Self {
+		// both trees keep their current version for now, but neither one can use it for changes anymore
+		SHARED_VERSIONS.fetch_max(new_version(), Ordering::Relaxed);
+		Self {
+			node_pointers: self.node_pointers.clone(),
+			node_fill_counts: self.node_fill_counts.clone(),
+			node_stubs: self.node_stubs.clone(),
+			node_parents: self.node_parents.clone(),
+			node_values: self.node_values.clone(),
+			node_generations: self.node_generations.clone(),
+			free_nodes: self.free_nodes.clone(),
+			node_versions: self.node_versions.clone(),
+			version: self.version,
+		}
+	}
+}
+
+
+
+/// A reference to a node within a SequenceTree
+pub struct SequenceTreeNode<'a, S, T> {
+	pub(crate) ref_tree: &'a SequenceTree<S, T>,
+	pub(crate) index: u32,
+}
+
+impl<'a, S: Symbol, T> SequenceTreeNode<'a, S, T> {
+	
+	/// Steps further into the tree and returns the value at the desired position (or None)
+	pub fn get(&self, key: impl AsRef<[S]>) -> Option<&'a T> {
+		let index = self.ref_tree.descend(self.index, key.as_ref())?;
+		self.ref_tree.node_values.get(index as usize)
+	}
+	/// Steps further into the tree and returns a new node reference (or None)
+	pub fn step(&self, key: impl AsRef<[S]>) -> Option<SequenceTreeNode<'a, S, T>> {
+		let index = self.ref_tree.descend(self.index, key.as_ref())?;
+		Some(Self {
+			ref_tree: self.ref_tree,
+			index,
+		})
+	}
+	
+	/// Returns the value at this node (or None)
+	pub fn value(&self) -> Option<&'a T> {
+		self.ref_tree.node_values.get(self.index as usize)
+	}
+	
+	/// Returns a handle to this node that stays valid until this node is removed
+	pub fn id(&self) -> NodeId {
+		self.ref_tree.id_of(self.index)
+	}
+	
+	/// Creates and returns the symbols that are needed to reach this node from the root node
+	pub fn path(&self) -> Vec<S> {
+		self.ref_tree.path_of(self.index)
+	}
+	
+	/// Iterates over the children of this node along with the symbol that leads to each one, sorted by symbol
+	pub fn children(&self) -> impl Iterator<Item = (S, SequenceTreeNode<'a, S, T>)> + use<'a, S, T> {
+		let ref_tree = self.ref_tree;
+		self.ref_tree.sorted_children(self.index).into_iter().map(move |(symbol, index)| (symbol, SequenceTreeNode {ref_tree, index}))
+	}
+	
+	/// Iterates over every key/value pair below this node (including this node), sorted by key
+	/// 
+	/// The keys are the full keys from the root node, not just the part below this node
+	pub fn iter(&self) -> impl Iterator<Item = (Vec<S>, &'a T)> + use<'a, S, T> {
+		self.ref_tree.iter_sequences_from(Some(self.index), self.path())
+	}
+	
+}
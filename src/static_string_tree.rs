@@ -0,0 +1,313 @@
+use crate::*;
+
+
+
+const NO_NODE: u32 = u32::MAX;
+
+/// A read-only StringTree that is built at compile time by the `string_tree!` macro, so it can be stored in a `static` or `const`
+/// 
+/// ```
+/// # use ::string_tree::{string_tree, StaticStringTree};
+/// # #[derive(Debug, PartialEq)]
+/// # enum Keyword {If, Else}
+/// static KEYWORDS: StaticStringTree<Keyword> = string_tree!{
+///     "if" => Keyword::If,
+///     "else" => Keyword::Else,
+/// };
+/// assert_eq!(KEYWORDS.get("else"), Some(&Keyword::Else));
+/// ```
+pub struct StaticStringTree<T: 'static> {
+	#[doc(hidden)]
+	pub nodes: &'static [StaticNode],
+	#[doc(hidden)]
+	pub values: &'static [T],
+}
+
+/// A node within a StaticStringTree, where each node's children are a linked list sorted by byte
+#[doc(hidden)]
+#[derive(Clone, Copy)]
+pub struct StaticNode {
+	pub(crate) byte: u8,
+	pub(crate) parent: u32,
+	pub(crate) first_child: u32,
+	pub(crate) next_sibling: u32,
+	pub(crate) value_index: u32, // the index into `values`, which is the index of the key in the macro
+}
+
+/// Creates a StaticStringTree at compile time from a list of `key => value` pairs, which should be used to initialize a `static` or `const`
+/// 
+/// Every key must be a `&str` constant and every value must be a constant expression, and duplicate keys are a compile error
+#[macro_export]
+macro_rules! string_tree {
+	($($key:expr => $value:expr),* $(,)?) => {
+		$crate::StaticStringTree {
+			nodes: &{
+				const KEYS: &[&str] = &[$($key),*];
+				const MAX_NODE_COUNT: usize = $crate::static_string_tree::max_node_count(KEYS);
+				const BUILT: ([$crate::StaticNode; MAX_NODE_COUNT], usize) = $crate::static_string_tree::build_nodes(KEYS);
+				const NODES: [$crate::StaticNode; BUILT.1] = $crate::static_string_tree::trim_nodes(&BUILT.0);
+				NODES
+			},
+			values: &[$($value),*],
+		}
+	};
+}
+
+/// Returns the most nodes that a tree with `keys` could need
+#[doc(hidden)]
+pub const fn max_node_count(keys: &[&str]) -> usize {
+	let mut output = 1;
+	let mut i = 0;
+	while i < keys.len() {
+		output += keys[i].len();
+		i += 1;
+	}
+	output
+}
+
+/// Builds the nodes for `keys` (where the value of `keys[i]` is `values[i]`), and returns them along with the number of nodes that were used
+#[doc(hidden)]
+pub const fn build_nodes<const MAX_NODE_COUNT: usize>(keys: &[&str]) -> ([StaticNode; MAX_NODE_COUNT], usize) {
+	let empty_node = StaticNode {
+		byte: 0,
+		parent: NO_NODE,
+		first_child: NO_NODE,
+		next_sibling: NO_NODE,
+		value_index: NO_NODE,
+	};
+	let mut nodes = [empty_node; MAX_NODE_COUNT];
+	let mut node_count = 1;
+	let mut key_index = 0;
+	while key_index < keys.len() {
+		let key = keys[key_index].as_bytes();
+		let mut node = 0;
+		let mut i = 0;
+		while i < key.len() {
+			let byte = key[i];
+			// find the child for `byte`, or the place in the sorted list of children where it should go
+			let mut prev_child = NO_NODE;
+			let mut child = nodes[node].first_child;
+			while child != NO_NODE && nodes[child as usize].byte < byte {
+				prev_child = child;
+				child = nodes[child as usize].next_sibling;
+			}
+			if child == NO_NODE || nodes[child as usize].byte != byte {
+				let new_child = node_count as u32;
+				nodes[new_child as usize] = StaticNode {
+					byte,
+					parent: node as u32,
+					first_child: NO_NODE,
+					next_sibling: child,
+					value_index: NO_NODE,
+				};
+				if prev_child == NO_NODE {
+					nodes[node].first_child = new_child;
+				} else {
+					nodes[prev_child as usize].next_sibling = new_child;
+				}
+				node_count += 1;
+				child = new_child;
+			}
+			node = child as usize;
+			i += 1;
+		}
+		if nodes[node].value_index != NO_NODE {
+			panic!("string_tree! has a duplicate key");
+		}
+		nodes[node].value_index = key_index as u32;
+		key_index += 1;
+	}
+	(nodes, node_count)
+}
+
+/// Returns the first `NODE_COUNT` nodes
+#[doc(hidden)]
+pub const fn trim_nodes<const MAX_NODE_COUNT: usize, const NODE_COUNT: usize>(nodes: &[StaticNode; MAX_NODE_COUNT]) -> [StaticNode; NODE_COUNT] {
+	let mut output = [nodes[0]; NODE_COUNT];
+	let mut i = 0;
+	while i < NODE_COUNT {
+		output[i] = nodes[i];
+		i += 1;
+	}
+	output
+}
+
+
+
+impl<T> StaticStringTree<T> {
+	
+	/// Gets a value from a given key (or None)
+	pub fn get(&self, key: impl AsRef<str>) -> Option<&'static T> {
+		self.root_node().get(key)
+	}
+	/// Returns true if the key has a value
+	pub fn contains_key(&self, key: impl AsRef<str>) -> bool {
+		self.get(key).is_some()
+	}
+	/// Steps further into the tree and returns a node reference (or None)
+	pub fn step(&self, key: impl AsRef<str>) -> Option<StaticStringTreeNode<T>> {
+		self.root_node().step(key)
+	}
+	/// Returns a reference to the root node
+	pub const fn root_node(&self) -> StaticStringTreeNode<T> {
+		StaticStringTreeNode {
+			tree: *self,
+			node: 0,
+		}
+	}
+	
+	/// Iterates over every key/value pair in the tree, sorted by key bytes
+	pub fn iter(&self) -> impl Iterator<Item = (String, &'static T)> + use<T> {
+		self.root_node().iter()
+	}
+	/// Iterates over every key/value pair whose key starts with `prefix`, sorted by key bytes
+	pub fn iter_prefix(&self, prefix: impl AsRef<str>) -> impl Iterator<Item = (String, &'static T)> {
+		let prefix = prefix.as_ref();
+		let start_node = self.step(prefix).map(|node| node.node);
+		self.iter_from(start_node, prefix.as_bytes().to_vec())
+	}
+	pub(crate) fn iter_from(&self, start_node: Option<u32>, start_path: Vec<u8>) -> impl Iterator<Item = (String, &'static T)> + use<T> {
+		let tree = *self;
+		IterableCoroutine(#[coroutine] move || {
+			let Some(start_node) = start_node else {return;};
+			let mut stack = vec!((start_node, start_path));
+			while let Some((node, path)) = stack.pop() {
+				for child in tree.children_of(node).into_iter().rev() {
+					let mut child_path = path.clone();
+					child_path.push(tree.nodes[child as usize].byte);
+					stack.push((child, child_path));
+				}
+				if let Some(value) = tree.value_of(node) {
+					// SAFETY: nodes with values always have a valid string as their path
+					yield (unsafe { String::from_utf8_unchecked(path) }, value);
+				}
+			}
+		})
+	}
+	
+	/// Returns the number of keys in the tree
+	pub const fn len(&self) -> usize {
+		self.values.len()
+	}
+	/// Returns true if there are no keys in the tree
+	pub const fn is_empty(&self) -> bool {
+		self.values.is_empty()
+	}
+	
+	/// Returns the child of `node` that is reached with `byte` (or None)
+	pub(crate) fn child(&self, node: u32, byte: u8) -> Option<u32> {
+		let mut child = self.nodes[node as usize].first_child;
+		while child != NO_NODE {
+			let child_byte = self.nodes[child as usize].byte;
+			if child_byte == byte {return Some(child);}
+			if child_byte > byte {return None;}
+			child = self.nodes[child as usize].next_sibling;
+		}
+		None
+	}
+	/// Returns the children of `node`, sorted by byte
+	pub(crate) fn children_of(&self, node: u32) -> Vec<u32> {
+		let mut output = vec!();
+		let mut child = self.nodes[node as usize].first_child;
+		while child != NO_NODE {
+			output.push(child);
+			child = self.nodes[child as usize].next_sibling;
+		}
+		output
+	}
+	pub(crate) fn value_of(&self, node: u32) -> Option<&'static T> {
+		let value_index = self.nodes[node as usize].value_index;
+		if value_index == NO_NODE {return None;}
+		Some(&self.values[value_index as usize])
+	}
+	
+}
+
+impl<T> Clone for StaticStringTree<T> {
+	fn clone(&self) -> Self {
+		*self
+	}
+}
+
+impl<T> Copy for StaticStringTree<T> {}
+
+
+
+/// A reference to a node within a StaticStringTree
+pub struct StaticStringTreeNode<T: 'static> {
+	pub(crate) tree: StaticStringTree<T>,
+	pub(crate) node: u32,
+}
+
+impl<T> StaticStringTreeNode<T> {
+	
+	/// Steps further into the tree and returns the value at the desired position (or None)
+	pub fn get(&self, key: impl AsRef<str>) -> Option<&'static T> {
+		self.step(key)?.value()
+	}
+	/// Steps further into the tree and returns a new node reference (or None)
+	pub fn step(&self, key: impl AsRef<str>) -> Option<StaticStringTreeNode<T>> {
+		let mut node = self.node;
+		for byte in key.as_ref().bytes() {
+			node = self.tree.child(node, byte)?;
+		}
+		Some(Self {
+			tree: self.tree,
+			node,
+		})
+	}
+	
+	/// Returns the value at this node (or None)
+	pub fn value(&self) -> Option<&'static T> {
+		self.tree.value_of(self.node)
+	}
+	
+	/// Creates and returns the string that is needed to reach this node from the root node
+	pub fn path(&self) -> String {
+		let mut string_bytes = vec!();
+		let mut node = self.node;
+		while node != 0 {
+			string_bytes.push(self.tree.nodes[node as usize].byte);
+			node = self.tree.nodes[node as usize].parent;
+		}
+		string_bytes.reverse();
+		unsafe {
+			// SAFETY: this result should be the path of this node, which itself should be a valid string
+			String::from_utf8_unchecked(string_bytes)
+		}
+	}
+	
+	/// Iterates over the children of this node, sorted by bytes
+	/// 
+	/// Note: for multi-byte characters, this does traverse deeper into the tree to ensure that the resulting node will have a valid `path()`
+	pub fn children(&self) -> impl Iterator<Item = StaticStringTreeNode<T>> + use<T> {
+		let tree = self.tree;
+		let start_node = self.node;
+		IterableCoroutine(#[coroutine] move || {
+			let mut stack = tree.children_of(start_node).into_iter().rev()
+				.map(|node| (node, (tree.nodes[node as usize].byte.leading_ones() as usize).saturating_sub(1)))
+				.collect::<Vec<_>>();
+			while let Some((node, remaining_bytes)) = stack.pop() {
+				if remaining_bytes == 0 {
+					yield StaticStringTreeNode {tree, node};
+					continue;
+				}
+				stack.extend(tree.children_of(node).into_iter().rev().map(|child| (child, remaining_bytes - 1)));
+			}
+		})
+	}
+	
+	/// Iterates over every key/value pair below this node (including this node), sorted by key bytes
+	pub fn iter(&self) -> impl Iterator<Item = (String, &'static T)> + use<T> {
+		self.tree.iter_from(Some(self.node), self.path().into_bytes())
+	}
+	
+}
+
+impl<T> Clone for StaticStringTreeNode<T> {
+	fn clone(&self) -> Self {
+		*self
+	}
+}
+
+impl<T> Copy for StaticStringTreeNode<T> {}
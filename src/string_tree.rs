@@ -1,31 +1,16 @@
 use crate::*;
 //use std::{fs::read, io::Write};
-use smallvec::SmallVec;
 
 
 
 /// A [trie](https://en.wikipedia.org/wiki/Trie) that maps strings to values. You can insert and remove items, view and edit any node, and traverse up and down the tree.
 /// 
+/// This is a SequenceTree of bytes, with an API that only allows valid strings as keys and paths.
+/// 
 /// To traverse this tree node-by-node, you must call `StringTree::root_node()` or `StringTree::root_node_mut()`.
-pub struct StringTree<T> {
-	pub(crate) node_pointers: Vec<SmallVec<[(u8, u32); 4]>>, // this could technically use a smaller u32 array but the way that it is right now probably eliminates bounds checking
-	pub(crate) node_fill_counts: Vec<u8>, // this technically can overflow, but even if it does, nothing bad happens (because a node is only removed when this value decreases to 0, and it can never overflow to anything above 0 because 256 is the maximum)
-	pub(crate) node_stubs: Vec<[u8; 16]>,
-	pub(crate) node_parents: Vec<(u32, u8)>, // (parent index, index within parent)
-	pub(crate) node_values: Vec<Option<T>>,
-}
+pub type StringTree<T> = SequenceTree<u8, T>;
 
 impl<T> StringTree<T> {
-	/// Creates a new, empty StringTree
-	pub fn new() -> Self {
-		Self {
-			node_pointers: vec!(SmallVec::new()),
-			node_fill_counts: vec!(0),
-			node_stubs: vec!([0; 16]),
-			node_parents: vec!((0, 0)),
-			node_values: vec!(None),
-		}
-	}
 	/// Creates a new StringTree with a given list of key/value pairs
 	pub fn from<S: AsRef<str>, I: IntoIterator<Item = (S, T)>>(source: I) -> Self {
 		let mut output = Self::new();
@@ -34,6 +19,39 @@ impl<T> StringTree<T> {
 		}
 		output
 	}
+	/// Creates a new StringTree from key/value pairs that are sorted by key bytes, or returns an error if the keys aren't sorted or have duplicates
+	/// 
+	/// This is much faster than `from()` because each key only needs to walk the part that differs from the previous key, and the nodes end up in depth-first order
+	pub fn from_sorted_iter<S: AsRef<str>, I: IntoIterator<Item = (S, T)>>(source: I) -> Result<Self, FromSortedError> {
+		let source = source.into_iter();
+		let mut output = Self::new();
+		output.reserve_nodes(source.size_hint().0);
+		let mut prev_key: Vec<u8> = vec!();
+		let mut path_nodes = vec!(0); // the node at each depth of the previous key
+		for (i, (key, value)) in source.enumerate() {
+			let key = key.as_ref();
+			let key_bytes = key.as_bytes();
+			if i > 0 {
+				match key_bytes.cmp(&prev_key) {
+					std::cmp::Ordering::Less => return Err(FromSortedError::Unsorted {index: i, key: key.to_string()}),
+					std::cmp::Ordering::Equal => return Err(FromSortedError::Duplicate {index: i, key: key.to_string()}),
+					std::cmp::Ordering::Greater => {}
+				}
+			}
+			let common_len = key_bytes.iter().zip(&prev_key).take_while(|(a, b)| a == b).count();
+			path_nodes.truncate(common_len + 1);
+			for &byte in &key_bytes[common_len..] {
+				// because the keys are sorted, new children always come after the existing children
+				let next_node = output.push_node(*path_nodes.last().unwrap(), byte, None);
+				path_nodes.push(next_node);
+			}
+			output.replace_value(*path_nodes.last().unwrap(), Some(value));
+			prev_key.clear();
+			prev_key.extend_from_slice(key_bytes);
+		}
+		output.check_invariants();
+		Ok(output)
+	}
 	/// Inserts a key/value pair into the tree, and returns the previous value if it exists
 	pub fn insert(&mut self, key: impl AsRef<str>, value: T) -> Option<T> {
 		self.root_node_mut().set(key, value)
@@ -50,6 +68,50 @@ impl<T> StringTree<T> {
 	pub fn remove(&mut self, key: impl AsRef<str>) -> Option<T> {
 		self.root_node_mut().remove(key)
 	}
+	/// Gets the value at the deepest node along `key` (including `key` itself) that has a value, along with the part of `key` that leads to it
+	/// 
+	/// This is useful for hierarchical data where a value at `org/team/` applies to everything below it unless it is overridden
+	pub fn get_inherited<'a, 'k>(&'a self, key: &'k str) -> Option<(&'k str, &'a T)> {
+		self.get_inherited_inner(key, None)
+	}
+	/// Same as `get_inherited()`, but only considers ancestors that end at a separator boundary
+	/// 
+	/// An ancestor is at a separator boundary if it is the root, the full key, ends with `separator`, or is directly followed by `separator` in `key`
+	pub fn get_inherited_with_separator<'a, 'k>(&'a self, key: &'k str, separator: char) -> Option<(&'k str, &'a T)> {
+		self.get_inherited_inner(key, Some(separator))
+	}
+	fn get_inherited_inner<'a, 'k>(&'a self, key: &'k str, separator: Option<char>) -> Option<(&'k str, &'a T)> {
+		let is_boundary = |len: usize| match separator {
+			None => true,
+			Some(separator) => len == 0 || len == key.len() || key[..len].ends_with(separator) || key[len..].starts_with(separator),
+		};
+		let mut output = self.node_values.get(0).map(|value| (&key[..0], value));
+		let mut curr_index = 0;
+		for (i, curr_byte) in key.bytes().enumerate() {
+			let Some(next_index) = self.child_index(curr_index, curr_byte) else {break;};
+			curr_index = next_index;
+			let len = i + 1;
+			// values can only exist at char boundaries, so slicing here cannot panic
+			if let Some(value) = self.node_values.get(curr_index as usize) && is_boundary(len) {
+				output = Some((&key[..len], value));
+			}
+		}
+		output
+	}
+	/// Iterates over every key/value pair in the tree, sorted by key bytes
+	pub fn iter(&self) -> impl Iterator<Item = (String, &T)> {
+		self.iter_from(Some(0), vec!())
+	}
+	/// Iterates over every key/value pair whose key starts with `prefix`, sorted by key bytes
+	pub fn iter_prefix(&self, prefix: impl AsRef<str>) -> impl Iterator<Item = (String, &T)> {
+		let prefix = prefix.as_ref();
+		let start_index = self.root_node().step(prefix).map(|node| node.index);
+		self.iter_from(start_index, prefix.as_bytes().to_vec())
+	}
+	pub(crate) fn iter_from(&self, start_index: Option<u32>, start_path: Vec<u8>) -> impl Iterator<Item = (String, &T)> {
+		// SAFETY: nodes with values always have a valid string as their path
+		self.iter_sequences_from(start_index, start_path).map(|(path, value)| (unsafe { String::from_utf8_unchecked(path) }, value))
+	}
 	/// Steps further into the tree and returns a node reference (or None)
 	pub fn step<'a>(&'a self, key: impl AsRef<str>) -> Option<StringTreeNode<'a, T>> {
 		self.root_node().step(key)
@@ -70,20 +132,96 @@ impl<T> StringTree<T> {
 			index: 0,
 		}
 	}
+	
+	/// Returns a reference to the node with the given id (or None if that node has been removed)
+	pub fn node<'a>(&'a self, id: NodeId) -> Option<StringTreeNode<'a, T>> {
+		if !self.is_id_valid(id) {return None;}
+		Some(StringTreeNode {
+			ref_tree: self,
+			index: id.index,
+		})
+	}
+	/// Returns a mutable reference to the node with the given id (or None if that node has been removed)
+	pub fn node_mut<'a>(&'a mut self, id: NodeId) -> Option<StringTreeNodeMut<'a, T>> {
+		if !self.is_id_valid(id) {return None;}
+		Some(StringTreeNodeMut {
+			ref_tree: self,
+			index: id.index,
+		})
+	}
+}
+
+/// A handle to a node that stays valid while the tree is edited, as long as the node itself isn't removed
+/// 
+/// Use `StringTreeNode::id()` to get one and `StringTree::node()` to turn it back into a node reference
+/// 
+/// Note: `StringTree::compact()` renumbers nodes and invalidates the ids of every node that moved
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NodeId {
+	pub(crate) index: u32,
+	pub(crate) generation: u32,
+}
+
+/// The amount of memory used by a StringTree, as returned by `StringTree::memory_usage()`
+/// 
+/// Every size is in bytes and includes allocated but unused capacity
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryReport {
+	/// The number of nodes in the tree (this is a count, not a size)
+	pub nodes: usize,
+	/// The children lists, including the ones that had to be moved to the heap
+	pub pointers: usize,
+	/// The number of children of each node
+	pub fill_counts: usize,
+	/// The stub of each node
+	pub stubs: usize,
+	/// The parent of each node
+	pub parents: usize,
+	/// The value slot of each node, which is only one bit per node for zero-sized values
+	pub values: usize,
+	/// The generation of each node slot and the list of free slots, which are used for NodeIds
+	pub generations: usize,
+	/// The version of each node, which is used by `diff()` to skip unchanged subtrees
+	pub versions: usize,
+}
+
+impl MemoryReport {
+	/// Returns the total number of bytes used
+	pub const fn total(&self) -> usize {
+		self.pointers + self.fill_counts + self.stubs + self.parents + self.values + self.generations + self.versions
+	}
+}
+
+/// The error returned by `StringTree::from_sorted_iter()`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FromSortedError {
+	/// The key at this index (of the input) is smaller than the previous key
+	Unsorted {
+		/// The position of the key within the input
+		index: usize,
+		/// The key itself
+		key: String,
+	},
+	/// The key at this index (of the input) is the same as the previous key
+	Duplicate {
+		/// The position of the key within the input
+		index: usize,
+		/// The key itself
+		key: String,
+	},
 }
 
-impl<T: Clone> Clone for StringTree<T> {
-	fn clone(&self) -> Self {
-		Self {
-			node_pointers: self.node_pointers.clone(),
-			node_fill_counts: self.node_fill_counts.clone(),
-			node_stubs: self.node_stubs.clone(),
-			node_parents: self.node_parents.clone(),
-			node_values: self.node_values.clone(),
+impl std::fmt::Display for FromSortedError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::Unsorted {index, key} => write!(f, "key \"{key}\" at index {index} is smaller than the previous key"),
+			Self::Duplicate {index, key} => write!(f, "key \"{key}\" at index {index} is the same as the previous key"),
 		}
 	}
 }
 
+impl std::error::Error for FromSortedError {}
+
 //impl<T: std::fmt::Debug> StringTree<T> {
 //	#[allow(unused)]
 //	pub(crate) fn print_self(&self) {
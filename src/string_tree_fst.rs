@@ -0,0 +1,31 @@
+use crate::*;
+
+
+
+impl StringTree<u64> {
+	
+	/// Builds a minimal finite-state transducer with the same keys and values as this tree, using the [fst](https://docs.rs/fst) crate
+	/// 
+	/// Shared prefixes and suffixes are both stored once and the values are spread along the transitions, so this is usually much smaller than the tree itself. The returned map supports `get()`, `range()` and prefix searches (with `fst::automaton::Str::starts_with()`), and its bytes (from `into_fst().into_inner()`) can be saved and loaded again with `fst::Map::new()`.
+	pub fn to_fst(&self) -> fst::Map<Vec<u8>> {
+		let mut builder = fst::MapBuilder::memory();
+		for (key, value) in self.iter() {
+			// `iter()` is sorted by key bytes with no duplicates, which is the only thing that `insert()` can fail on
+			builder.insert(key, *value).expect("keys should be sorted and unique");
+		}
+		fst::Map::new(builder.into_inner().expect("writing to a Vec cannot fail")).expect("the builder should create a valid fst")
+	}
+	
+	/// Creates a new StringTree with every key/value pair from an fst map, or an error if any key isn't valid UTF-8
+	pub fn from_fst<D: AsRef<[u8]>>(map: &fst::Map<D>) -> Result<Self, std::string::FromUtf8Error> {
+		use fst::Streamer;
+		let mut pairs = vec!();
+		let mut stream = map.stream();
+		while let Some((key, value)) = stream.next() {
+			pairs.push((String::from_utf8(key.to_vec())?, value));
+		}
+		// fst maps are always sorted by key bytes with no duplicates
+		Ok(Self::from_sorted_iter(pairs).expect("fst keys should be sorted and unique"))
+	}
+	
+}
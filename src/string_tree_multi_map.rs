@@ -0,0 +1,113 @@
+use crate::*;
+use smallvec::SmallVec;
+
+
+
+/// A StringTree where each key holds a list of values instead of a single value
+pub struct StringTreeMultiMap<T> {
+	pub(crate) tree: StringTree<SmallVec<[T; 1]>>,
+	pub(crate) len: usize,
+}
+
+impl<T> StringTreeMultiMap<T> {
+	
+	/// Creates a new, empty StringTreeMultiMap
+	pub fn new() -> Self {
+		Self {
+			tree: StringTree::new(),
+			len: 0,
+		}
+	}
+	/// Creates a new StringTreeMultiMap with a given list of key/value pairs (duplicate keys have their values appended in order)
+	pub fn from<S: AsRef<str>, I: IntoIterator<Item = (S, T)>>(source: I) -> Self {
+		let mut output = Self::new();
+		for (key, value) in source {
+			output.insert(key, value);
+		}
+		output
+	}
+	
+	/// Appends a value to the list of values at a given key
+	pub fn insert(&mut self, key: impl AsRef<str>, value: T) {
+		self.len += 1;
+		if let Some(values) = self.tree.get_mut(&key) {
+			values.push(value);
+			return;
+		}
+		let mut values = SmallVec::new();
+		values.push(value);
+		self.tree.insert(key, values);
+	}
+	/// Returns every value at a given key (or an empty slice if there are none)
+	pub fn get_all(&self, key: impl AsRef<str>) -> &[T] {
+		self.tree.get(key).map(|values| values.as_slice()).unwrap_or(&[])
+	}
+	/// Returns every value at a given key as mut (or an empty slice if there are none)
+	pub fn get_all_mut(&mut self, key: impl AsRef<str>) -> &mut [T] {
+		self.tree.get_mut(key).map(|values| values.as_mut_slice()).unwrap_or(&mut [])
+	}
+	/// Returns true if there is at least one value at a given key
+	pub fn contains_key(&self, key: impl AsRef<str>) -> bool {
+		self.tree.get(key).is_some()
+	}
+	
+	/// Removes and returns the first value at a given key that matches `pred` (or None)
+	/// 
+	/// If this removes the last value at the key, the key is removed as well
+	pub fn remove_one(&mut self, key: impl AsRef<str>, pred: impl FnMut(&T) -> bool) -> Option<T> {
+		let key = key.as_ref();
+		let values = self.tree.get_mut(key)?;
+		let position = values.iter().position(pred)?;
+		let output = values.remove(position);
+		if values.is_empty() {
+			self.tree.remove(key);
+		}
+		self.len -= 1;
+		Some(output)
+	}
+	/// Removes and returns every value at a given key (or an empty Vec if there are none)
+	pub fn remove_all(&mut self, key: impl AsRef<str>) -> Vec<T> {
+		let Some(values) = self.tree.remove(key) else {return vec!();};
+		self.len -= values.len();
+		values.into_vec()
+	}
+	
+	/// Returns the number of values in the map, counting every value at every key
+	pub const fn len(&self) -> usize {
+		self.len
+	}
+	/// Returns true if there are no values in the map
+	pub const fn is_empty(&self) -> bool {
+		self.len == 0
+	}
+	
+	/// Iterates over every key and its values, sorted by key bytes
+	pub fn iter(&self) -> impl Iterator<Item = (String, &[T])> {
+		self.tree.iter().map(|(key, values)| (key, values.as_slice()))
+	}
+	/// Iterates over every key that starts with `prefix` and its values, sorted by key bytes
+	pub fn iter_prefix(&self, prefix: impl AsRef<str>) -> impl Iterator<Item = (String, &[T])> {
+		self.tree.iter_prefix(prefix).map(|(key, values)| (key, values.as_slice()))
+	}
+	
+	/// Returns the underlying tree, which can be used for node-by-node traversal
+	pub const fn as_tree(&self) -> &StringTree<SmallVec<[T; 1]>> {
+		&self.tree
+	}
+	
+}
+
+impl<T> Default for StringTreeMultiMap<T> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<T: Clone> Clone for StringTreeMultiMap<T> {
+	fn clone(&self) -> Self {
+		Self {
+			tree: self.tree.clone(),
+			len: self.len,
+		}
+	}
+}
@@ -14,7 +14,7 @@ impl<'a, T> StringTreeNode<'a, T> {
 	pub fn get(&self, key: impl AsRef<str>) -> Option<&'a T> {
 		let key = key.as_ref().as_bytes();
 		let index = self.get_index_of_key(key)?;
-		self.ref_tree.node_values[index as usize].as_ref()
+		self.ref_tree.node_values.get(index as usize)
 	}
 	/// Steps further into the tree and returns the value at the desired position (or an error)
 	/// 
@@ -22,7 +22,7 @@ impl<'a, T> StringTreeNode<'a, T> {
 	pub fn try_get(&self, key: impl AsRef<str>) -> Result<&'a T, String> {
 		let key = key.as_ref();
 		let index = self.get_index_of_key(key.as_bytes()).ok_or_else(|| self.path() + key)?;
-		self.ref_tree.node_values[index as usize].as_ref().ok_or_else(|| self.path() + key)
+		self.ref_tree.node_values.get(index as usize).ok_or_else(|| self.path() + key)
 	}
 	
 	/// Steps further into the tree and returns a new node reference (or None)
@@ -49,119 +49,96 @@ impl<'a, T> StringTreeNode<'a, T> {
 	fn get_index_of_key(&self, key: &[u8]) -> Option<u32> {
 		let mut curr_index = self.index;
 		for curr_byte in key {
-			let curr_pointers = self.ref_tree.node_pointers[curr_index as usize];
-			curr_index = curr_pointers[*curr_byte as usize];
-			if curr_index == 0 {return None;}
+			curr_index = self.ref_tree.child_index(curr_index, *curr_byte)?;
 		}
 		Some(curr_index)
 	}
 	
 	/// Returns the value at this node (or None)
 	pub fn value(&self) -> Option<&'a T> {
-		self.ref_tree.node_values[self.index as usize].as_ref()
+		self.ref_tree.node_values.get(self.index as usize)
 	}
 	/// Returns the value at this node (or an error)
 	/// 
 	/// The error value is the path of the current node
 	pub fn value_result(&self) -> Result<&'a T, String> {
-		// SAFETY: pointer reads are safe because of the bounds checks
-		// SAFETY: unwrapping is safe because of the `is_some()` check
-		unsafe {
-			if self.index as usize >= self.ref_tree.node_values.len() {panic!("index out of bounds: the length is {} but the index is {}", self.ref_tree.node_values.len(), self.index);}
-			let ptr = self.ref_tree.node_values.as_ptr().add(self.index as usize);
-			if (*ptr).is_some() {
-				Ok((*ptr).as_ref().unwrap_unchecked())
-			} else {
-				Err(self.path())
-			}
-		}
+		self.ref_tree.node_values.get(self.index as usize).ok_or_else(|| self.path())
+	}
+	
+	/// Returns a handle to this node that stays valid until this node is removed
+	pub fn id(&self) -> NodeId {
+		self.ref_tree.id_of(self.index)
 	}
 	
 	/// Creates and returns the string that is needed to reach this node from the root node
 	pub fn path(&self) -> String {
-		let mut string_bytes = vec!();
+		unsafe {
+			// SAFETY: this result should be the path of this node, which itself should be a valid string
+			String::from_utf8_unchecked(self.path_bytes())
+		}
+	}
+	/// Creates and returns the bytes that are needed to reach this node from the root node
+	/// 
+	/// Unlike `path()`, this is also valid for nodes in the middle of a multi-byte char
+	pub fn path_bytes(&self) -> Vec<u8> {
+		self.ref_tree.path_of(self.index)
+	}
+	
+	/// Returns the value at this node or the value of its nearest ancestor that has one (or None)
+	pub fn inherited_value(&self) -> Option<&'a T> {
+		let mut i = self.index as usize;
+		loop {
+			if let Some(value) = self.ref_tree.node_values.get(i) {return Some(value);}
+			if i == 0 {return None;}
+			i = self.ref_tree.node_parents[i].0 as usize;
+		}
+	}
+	/// Same as `inherited_value()`, but only considers ancestors that end at a separator boundary
+	/// 
+	/// An ancestor is at a separator boundary if it is the root, this node, ends with `separator`, or is directly followed by `separator` in this node's path
+	pub fn inherited_value_with_separator(&self, separator: char) -> Option<&'a T> {
+		let mut separator_bytes = [0; 4];
+		let separator_bytes = separator.encode_utf8(&mut separator_bytes).as_bytes();
+		// walk up once to get the path bytes, then check each ancestor from deepest to shallowest
+		let mut ancestors = vec!();
+		let mut path_bytes = vec!();
 		let mut i = self.index as usize;
 		while i != 0 {
 			let (parent_index, index_within_parent) = self.ref_tree.node_parents[i];
-			string_bytes.push(index_within_parent);
+			ancestors.push(i);
+			path_bytes.push(index_within_parent);
 			i = parent_index as usize;
 		}
-		string_bytes.reverse();
-		unsafe {
-			// SAFETY: this result should be the path of this node, which itself should be a valid string
-			String::from_utf8_unchecked(string_bytes)
+		ancestors.push(0);
+		path_bytes.reverse();
+		for (depth_from_self, &node_index) in ancestors.iter().enumerate() {
+			let Some(value) = self.ref_tree.node_values.get(node_index) else {continue;};
+			let len = path_bytes.len() - depth_from_self;
+			if len == 0 || depth_from_self == 0 || path_bytes[..len].ends_with(separator_bytes) || path_bytes[len..].starts_with(separator_bytes) {
+				return Some(value);
+			}
 		}
+		None
 	}
 	
 	/// Iterates over the children of this node.
 	/// 
 	/// Note: for multi-byte characters, this does traverse deeper into the tree to ensure that the resulting StringTreeNode will have a valid `path()`
 	pub fn children(&self) -> impl Iterator<Item = StringTreeNode<'a, T>> {
-		IterableCoroutine(#[coroutine] || {
-			let node_0 = self.index;
-			// single-byte chars
-			for i in 0b00000000..=0b01111111 {
-				let node_1 = self.ref_tree.node_pointers[node_0 as usize][i];
-				if node_1 == 0 {continue;}
-				// SAFETY: this result is guaranteed to be a different section of the tree from all other yields, similar to Vec's `split_at_mut`
-				yield StringTreeNode {
-					ref_tree: self.ref_tree,
-					index: node_1,
-				};
-			}
-			// 2-byte chars
-			for i0 in 0b11000000..=0b11011111 {
-				let node_1 = self.ref_tree.node_pointers[node_0 as usize][i0];
-				if node_1 == 0 {continue;}
-				for i1 in 0b10000000..=0b10111111 {
-					let node_2 = self.ref_tree.node_pointers[node_1 as usize][i1];
-					if node_2 == 0 {continue;}
+		let ref_tree = self.ref_tree;
+		let start_index = self.index;
+		IterableCoroutine(#[coroutine] move || {
+			// each entry is (node index, number of continuation bytes left before the end of the char)
+			let mut stack = ref_tree.sorted_children(start_index).into_iter().rev()
+				.map(|(byte, index)| (index, (byte.leading_ones() as usize).saturating_sub(1)))
+				.collect::<Vec<_>>();
+			while let Some((index, remaining_bytes)) = stack.pop() {
+				if remaining_bytes == 0 {
 					// SAFETY: this result is guaranteed to be a different section of the tree from all other yields, similar to Vec's `split_at_mut`
-					yield StringTreeNode {
-						ref_tree: self.ref_tree,
-						index: node_2,
-					};
-				}
-			}
-			// 3-byte chars
-			for i0 in 0b11100000..=0b11101111 {
-				let node_1 = self.ref_tree.node_pointers[node_0 as usize][i0];
-				if node_1 == 0 {continue;}
-				for i1 in 0b10000000..=0b10111111 {
-					let node_2 = self.ref_tree.node_pointers[node_1 as usize][i1];
-					if node_2 == 0 {continue;}
-					for i2 in 0b10000000..=0b10111111 {
-						let node_3 = self.ref_tree.node_pointers[node_2 as usize][i2];
-						if node_3 == 0 {continue;}
-						// SAFETY: this result is guaranteed to be a different section of the tree from all other yields, similar to Vec's `split_at_mut`
-						yield StringTreeNode {
-							ref_tree: self.ref_tree,
-							index: node_3,
-						};
-					}
-				}
-			}
-			// 4-byte chars
-			for i0 in 0b11110000..=0b11110111 {
-				let node_1 = self.ref_tree.node_pointers[node_0 as usize][i0];
-				if node_1 == 0 {continue;}
-				for i1 in 0b10000000..=0b10111111 {
-					let node_2 = self.ref_tree.node_pointers[node_1 as usize][i1];
-					if node_2 == 0 {continue;}
-					for i2 in 0b10000000..=0b10111111 {
-						let node_3 = self.ref_tree.node_pointers[node_2 as usize][i2];
-						if node_3 == 0 {continue;}
-						for i3 in 0b10000000..=0b10111111 {
-							let node_4 = self.ref_tree.node_pointers[node_3 as usize][i3];
-							if node_4 == 0 {continue;}
-							// SAFETY: this result is guaranteed to be a different section of the tree from all other yields, similar to Vec's `split_at_mut`
-							yield StringTreeNode {
-								ref_tree: self.ref_tree,
-								index: node_4,
-							};
-						}
-					}
+					yield StringTreeNode {ref_tree, index};
+					continue;
 				}
+				stack.extend(ref_tree.sorted_children(index).into_iter().rev().map(|(_, child_index)| (child_index, remaining_bytes - 1)));
 			}
 		})
 	}
@@ -0,0 +1,174 @@
+use crate::*;
+
+
+
+/// A set of strings, stored as a StringTree without values
+/// 
+/// This wraps a `StringTree<()>`, and since `()` is zero-sized, the tree only stores one bit per node for whether it is the end of a key instead of a whole value slot. For a smaller set that can't be edited, see `minimize()`.
+/// 
+/// The set operations (`union()`, `intersection()`, etc) walk both trees in lockstep, so subtrees that only exist in one set are copied or skipped as a whole instead of key-by-key
+pub struct StringTreeSet {
+	pub(crate) tree: StringTree<()>,
+}
+
+impl StringTreeSet {
+	
+	/// Creates a new, empty StringTreeSet
+	pub fn new() -> Self {
+		Self {
+			tree: StringTree::new(),
+		}
+	}
+	/// Creates a new StringTreeSet with a given list of keys
+	pub fn from<S: AsRef<str>, I: IntoIterator<Item = S>>(source: I) -> Self {
+		let mut output = Self::new();
+		for key in source {
+			output.insert(key);
+		}
+		output
+	}
+	
+	/// Inserts a key into the set, and returns true if it wasn't already in the set
+	pub fn insert(&mut self, key: impl AsRef<str>) -> bool {
+		self.tree.insert(key, ()).is_none()
+	}
+	/// Returns true if the key is in the set
+	pub fn contains(&self, key: impl AsRef<str>) -> bool {
+		self.tree.get(key).is_some()
+	}
+	/// Removes a key from the set, and returns true if it was in the set
+	pub fn remove(&mut self, key: impl AsRef<str>) -> bool {
+		self.tree.remove(key).is_some()
+	}
+	
+	/// Iterates over every key in the set, sorted by key bytes
+	pub fn iter(&self) -> impl Iterator<Item = String> {
+		self.tree.iter().map(|(key, _)| key)
+	}
+	/// Iterates over every key in the set that starts with `prefix`, sorted by key bytes
+	pub fn iter_prefix(&self, prefix: impl AsRef<str>) -> impl Iterator<Item = String> {
+		self.tree.iter_prefix(prefix).map(|(key, _)| key)
+	}
+	
+	/// Returns the underlying tree, which can be used for node-by-node traversal
+	pub const fn as_tree(&self) -> &StringTree<()> {
+		&self.tree
+	}
+	
+	/// Returns a new set with every key that is in `self` or `other`
+	pub fn union(&self, other: &Self) -> Self {
+		self.combine(other, |in_self, in_other| in_self || in_other)
+	}
+	/// Returns a new set with every key that is in both `self` and `other`
+	pub fn intersection(&self, other: &Self) -> Self {
+		self.combine(other, |in_self, in_other| in_self && in_other)
+	}
+	/// Returns a new set with every key that is in `self` but not in `other`
+	pub fn difference(&self, other: &Self) -> Self {
+		self.combine(other, |in_self, in_other| in_self && !in_other)
+	}
+	/// Returns a new set with every key that is in exactly one of `self` and `other`
+	pub fn symmetric_difference(&self, other: &Self) -> Self {
+		self.combine(other, |in_self, in_other| in_self != in_other)
+	}
+	/// Returns true if every key in `self` is also in `other`
+	pub fn is_subset(&self, other: &Self) -> bool {
+		let mut stack = vec!((0, 0));
+		while let Some((self_index, other_index)) = stack.pop() {
+			if self.tree.node_values.is_some(self_index as usize) && other.tree.node_values.is_none(other_index as usize) {return false;}
+			for &(byte, self_child) in &self.tree.node_pointers[self_index as usize] {
+				// every subtree has at least one key, so a subtree that `other` is missing means a missing key
+				let Some(other_child) = other.tree.child_index(other_index, byte) else {return false;};
+				stack.push((self_child, other_child));
+			}
+		}
+		true
+	}
+	/// Returns true if every key in `other` is also in `self`
+	pub fn is_superset(&self, other: &Self) -> bool {
+		other.is_subset(self)
+	}
+	
+	/// Walks both trees in lockstep and builds a new set, where `keep` decides whether a key should be kept based on whether it is in `self` and `other`
+	fn combine(&self, other: &Self, keep: fn(bool, bool) -> bool) -> Self {
+		let (a, b) = (&self.tree, &other.tree);
+		let mut output = Self::new();
+		// the byte and output node at each depth of the current path, where output nodes are only added once a key below them is kept so that nothing has to be removed again
+		let mut path = vec!();
+		// each entry is a node in `a` and `b` at the same path, along with its depth and the byte that leads to it
+		let mut stack = vec!((0, 0, 0usize, 0));
+		while let Some((a_index, b_index, depth, byte)) = stack.pop() {
+			path.truncate(depth);
+			path.push((byte, if depth == 0 {Some(0)} else {None}));
+			if keep(a.node_values.is_some(a_index as usize), b.node_values.is_some(b_index as usize)) {
+				let output_index = Self::add_path(&mut output.tree, &mut path);
+				output.tree.replace_value(output_index, Some(()));
+			}
+			let a_children = a.sorted_children(a_index);
+			let b_children = b.sorted_children(b_index);
+			let (mut a_i, mut b_i) = (0, 0);
+			let mut next_entries = vec!();
+			while a_i < a_children.len() || b_i < b_children.len() {
+				let a_byte = a_children.get(a_i).map(|(byte, _)| *byte);
+				let b_byte = b_children.get(b_i).map(|(byte, _)| *byte);
+				match (a_byte, b_byte) {
+					(Some(a_byte), Some(b_byte)) if a_byte == b_byte => {
+						next_entries.push((a_children[a_i].1, b_children[b_i].1, depth + 1, a_byte));
+						a_i += 1;
+						b_i += 1;
+					}
+					(Some(a_byte), b_byte) if b_byte.is_none_or(|b_byte| a_byte < b_byte) => {
+						if keep(true, false) {
+							let output_index = Self::add_path(&mut output.tree, &mut path);
+							Self::copy_subtree(&mut output.tree, output_index, a_byte, a, a_children[a_i].1);
+						}
+						a_i += 1;
+					}
+					_ => {
+						if keep(false, true) {
+							let output_index = Self::add_path(&mut output.tree, &mut path);
+							Self::copy_subtree(&mut output.tree, output_index, b_children[b_i].0, b, b_children[b_i].1);
+						}
+						b_i += 1;
+					}
+				}
+			}
+			stack.extend(next_entries.into_iter().rev());
+		}
+		output
+	}
+	/// Adds the output nodes of `path` that haven't been added yet, and returns the output node at the end of it
+	/// 
+	/// The root node is always added, so only the nodes after the last added one have to be checked
+	fn add_path(output: &mut StringTree<()>, path: &mut [(u8, Option<u32>)]) -> u32 {
+		let added_len = path.iter().rposition(|(_, output_index)| output_index.is_some()).unwrap() + 1;
+		let mut output_index = path[added_len - 1].1.unwrap();
+		for (byte, path_output_index) in &mut path[added_len..] {
+			output_index = output.push_node(output_index, *byte, None);
+			*path_output_index = Some(output_index);
+		}
+		output_index
+	}
+	fn copy_subtree(output: &mut StringTree<()>, output_parent: u32, byte: u8, source: &StringTree<()>, source_index: u32) {
+		let mut stack = vec!((output_parent, byte, source_index));
+		while let Some((output_parent, byte, source_index)) = stack.pop() {
+			let output_index = output.push_node(output_parent, byte, source.node_values.is_some(source_index as usize).then_some(()));
+			stack.extend(source.sorted_children(source_index).into_iter().rev().map(|(child_byte, child_index)| (output_index, child_byte, child_index)));
+		}
+	}
+	
+}
+
+impl Default for StringTreeSet {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl Clone for StringTreeSet {
+	fn clone(&self) -> Self {
+		Self {
+			tree: self.tree.clone(),
+		}
+	}
+}
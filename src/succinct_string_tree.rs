@@ -0,0 +1,312 @@
+use crate::*;
+use std::collections::VecDeque;
+
+
+
+/// A read-only version of StringTree that uses as little memory as possible, by storing its shape as a [LOUDS](https://en.wikipedia.org/wiki/Succinct_data_structure) bit vector
+/// 
+/// The shape of the tree takes about 2 bits per node, plus one byte per node for the bytes of the keys and one bit per node for whether it has a value. Lookups are slower than StringTree because each step needs a few rank/select queries on the bit vectors.
+pub struct SuccinctStringTree<T> {
+	pub(crate) louds: BitVector, // "10", then for each node in breadth-first order, a 1 for each child followed by a 0
+	pub(crate) labels: Vec<u8>, // the byte that leads to each node (other than the root node), in breadth-first order
+	pub(crate) terminals: BitVector, // whether each node has a value
+	pub(crate) values: Vec<T>,
+}
+
+impl<T> StringTree<T> {
+	/// Converts this tree into a SuccinctStringTree, which can't be edited but uses much less memory
+	pub fn into_succinct(mut self) -> SuccinctStringTree<T> {
+		let mut louds = BitVectorBuilder::default();
+		let mut labels = vec!();
+		let mut terminals = BitVectorBuilder::default();
+		let mut values = vec!();
+		louds.push(true);
+		louds.push(false);
+		let mut queue = VecDeque::from([0]);
+		while let Some(index) = queue.pop_front() {
+			if let Some(value) = self.node_values.take(index as usize) {
+				values.push(value);
+				terminals.push(true);
+			} else {
+				terminals.push(false);
+			}
+			for (byte, child_index) in self.sorted_children(index) {
+				louds.push(true);
+				labels.push(byte);
+				queue.push_back(child_index);
+			}
+			louds.push(false);
+		}
+		labels.shrink_to_fit();
+		values.shrink_to_fit();
+		SuccinctStringTree {
+			louds: louds.build(),
+			labels,
+			terminals: terminals.build(),
+			values,
+		}
+	}
+}
+
+impl<T> SuccinctStringTree<T> {
+	
+	/// Gets a value from a given key (or None)
+	pub fn get(&self, key: impl AsRef<str>) -> Option<&T> {
+		self.root_node().get(key)
+	}
+	/// Returns true if the key has a value
+	pub fn contains_key(&self, key: impl AsRef<str>) -> bool {
+		self.get(key).is_some()
+	}
+	/// Steps further into the tree and returns a node reference (or None)
+	pub fn step<'a>(&'a self, key: impl AsRef<str>) -> Option<SuccinctStringTreeNode<'a, T>> {
+		self.root_node().step(key)
+	}
+	pub const fn root_node<'a>(&'a self) -> SuccinctStringTreeNode<'a, T> {
+		SuccinctStringTreeNode {
+			ref_tree: self,
+			node: 0,
+		}
+	}
+	
+	/// Iterates over every key/value pair in the tree, sorted by key bytes
+	pub fn iter(&self) -> impl Iterator<Item = (String, &T)> {
+		self.iter_from(Some(0), vec!())
+	}
+	/// Iterates over every key/value pair whose key starts with `prefix`, sorted by key bytes
+	pub fn iter_prefix(&self, prefix: impl AsRef<str>) -> impl Iterator<Item = (String, &T)> {
+		let prefix = prefix.as_ref();
+		let start_node = self.step(prefix).map(|node| node.node);
+		self.iter_from(start_node, prefix.as_bytes().to_vec())
+	}
+	pub(crate) fn iter_from(&self, start_node: Option<u32>, start_path: Vec<u8>) -> impl Iterator<Item = (String, &T)> {
+		IterableCoroutine(#[coroutine] move || {
+			let Some(start_node) = start_node else {return;};
+			let mut stack = vec!((start_node, start_path));
+			while let Some((node, path)) = stack.pop() {
+				let (first_child, child_count) = self.children_of(node);
+				for child in (first_child..first_child + child_count).rev() {
+					let mut child_path = path.clone();
+					child_path.push(self.labels[child as usize - 1]);
+					stack.push((child, child_path));
+				}
+				if let Some(value) = self.value_of(node) {
+					// SAFETY: nodes with values always have a valid string as their path
+					yield (unsafe { String::from_utf8_unchecked(path) }, value);
+				}
+			}
+		})
+	}
+	
+	/// Returns the number of keys in the tree
+	pub fn len(&self) -> usize {
+		self.values.len()
+	}
+	/// Returns true if there are no keys in the tree
+	pub fn is_empty(&self) -> bool {
+		self.values.is_empty()
+	}
+	/// Returns the number of bytes that this tree has allocated (not including any memory that the values themselves have allocated)
+	pub fn memory_usage(&self) -> usize {
+		self.louds.memory_usage() + self.labels.capacity() + self.terminals.memory_usage() + self.values.capacity() * size_of::<T>()
+	}
+	
+	/// Returns the first child and the number of children of `node`
+	/// 
+	/// Node `x`'s children are listed right after the `x`th 0 in `louds`, and since nodes are numbered in breadth-first order, they are numbered consecutively
+	pub(crate) fn children_of(&self, node: u32) -> (u32, u32) {
+		let start = self.louds.select0(node as usize) + 1;
+		let end = self.louds.select0(node as usize + 1);
+		(self.louds.rank1(start) as u32, (end - start) as u32)
+	}
+	/// Returns the child of `node` that is reached with `byte` (or None)
+	pub(crate) fn child(&self, node: u32, byte: u8) -> Option<u32> {
+		let (first_child, child_count) = self.children_of(node);
+		let labels = &self.labels[first_child as usize - 1 .. (first_child + child_count) as usize - 1];
+		labels.binary_search(&byte).ok().map(|offset| first_child + offset as u32)
+	}
+	/// Returns the parent of `node`, which must not be the root node
+	pub(crate) fn parent(&self, node: u32) -> u32 {
+		self.louds.rank0(self.louds.select1(node as usize)) as u32 - 1
+	}
+	pub(crate) fn value_of(&self, node: u32) -> Option<&T> {
+		if !self.terminals.get(node as usize) {return None;}
+		Some(&self.values[self.terminals.rank1(node as usize)])
+	}
+	
+}
+
+impl<T: Clone> Clone for SuccinctStringTree<T> {
+	fn clone(&self) -> Self {
+		Self {
+			louds: self.louds.clone(),
+			labels: self.labels.clone(),
+			terminals: self.terminals.clone(),
+			values: self.values.clone(),
+		}
+	}
+}
+
+
+
+/// A reference to a node within a SuccinctStringTree
+pub struct SuccinctStringTreeNode<'a, T> {
+	pub(crate) ref_tree: &'a SuccinctStringTree<T>,
+	pub(crate) node: u32,
+}
+
+impl<'a, T> SuccinctStringTreeNode<'a, T> {
+	
+	/// Steps further into the tree and returns the value at the desired position (or None)
+	pub fn get(&self, key: impl AsRef<str>) -> Option<&'a T> {
+		self.step(key)?.value()
+	}
+	/// Steps further into the tree and returns a new node reference (or None)
+	pub fn step(&self, key: impl AsRef<str>) -> Option<SuccinctStringTreeNode<'a, T>> {
+		let mut node = self.node;
+		for byte in key.as_ref().bytes() {
+			node = self.ref_tree.child(node, byte)?;
+		}
+		Some(Self {
+			ref_tree: self.ref_tree,
+			node,
+		})
+	}
+	
+	/// Returns the value at this node (or None)
+	pub fn value(&self) -> Option<&'a T> {
+		self.ref_tree.value_of(self.node)
+	}
+	
+	/// Creates and returns the string that is needed to reach this node from the root node
+	pub fn path(&self) -> String {
+		let mut string_bytes = vec!();
+		let mut node = self.node;
+		while node != 0 {
+			string_bytes.push(self.ref_tree.labels[node as usize - 1]);
+			node = self.ref_tree.parent(node);
+		}
+		string_bytes.reverse();
+		unsafe {
+			// SAFETY: this result should be the path of this node, which itself should be a valid string
+			String::from_utf8_unchecked(string_bytes)
+		}
+	}
+	
+	/// Iterates over every key/value pair below this node (including this node), sorted by key bytes
+	pub fn iter(&self) -> impl Iterator<Item = (String, &'a T)> + use<'a, T> {
+		self.ref_tree.iter_from(Some(self.node), self.path().into_bytes())
+	}
+	
+}
+
+
+
+const WORDS_PER_BLOCK: usize = 8;
+
+/// A bit vector with rank and select queries
+/// 
+/// The number of ones before every block of 512 bits is stored, so rank takes a few popcounts and select takes a binary search over the blocks
+#[derive(Clone)]
+pub(crate) struct BitVector {
+	pub(crate) words: Vec<u64>,
+	pub(crate) len: usize,
+	pub(crate) block_ranks: Vec<u32>, // the number of ones before each block, plus one extra entry at the end
+}
+
+impl BitVector {
+	pub(crate) fn get(&self, index: usize) -> bool {
+		debug_assert!(index < self.len);
+		self.words[index / 64] >> (index % 64) & 1 == 1
+	}
+	/// Returns the number of ones before `index`
+	pub(crate) fn rank1(&self, index: usize) -> usize {
+		let word_index = index / 64;
+		let block_index = word_index / WORDS_PER_BLOCK;
+		let mut output = self.block_ranks[block_index] as usize;
+		for word in &self.words[block_index * WORDS_PER_BLOCK .. word_index] {
+			output += word.count_ones() as usize;
+		}
+		if !index.is_multiple_of(64) {
+			output += (self.words[word_index] & ((1 << (index % 64)) - 1)).count_ones() as usize;
+		}
+		output
+	}
+	/// Returns the number of zeros before `index`
+	pub(crate) fn rank0(&self, index: usize) -> usize {
+		index - self.rank1(index)
+	}
+	/// Returns the position of the one with the given rank (starting at 0)
+	pub(crate) fn select1(&self, rank: usize) -> usize {
+		self.select(rank, |block_index| self.block_ranks[block_index] as usize, |word| word)
+	}
+	/// Returns the position of the zero with the given rank (starting at 0)
+	pub(crate) fn select0(&self, rank: usize) -> usize {
+		self.select(rank, |block_index| block_index * WORDS_PER_BLOCK * 64 - self.block_ranks[block_index] as usize, |word| !word)
+	}
+	fn select(&self, rank: usize, count_before_block: impl Fn(usize) -> usize, map_word: impl Fn(u64) -> u64) -> usize {
+		// binary search for the last block that starts before the wanted bit
+		let (mut low, mut high) = (0, self.block_ranks.len());
+		while high - low > 1 {
+			let middle = (low + high) / 2;
+			if count_before_block(middle) <= rank {
+				low = middle;
+			} else {
+				high = middle;
+			}
+		}
+		let block_index = low;
+		let mut remaining = rank - count_before_block(block_index);
+		let mut word_index = block_index * WORDS_PER_BLOCK;
+		loop {
+			let mut word = map_word(self.words[word_index]);
+			let count = word.count_ones() as usize;
+			if remaining < count {
+				for _ in 0..remaining {
+					word &= word - 1;
+				}
+				return word_index * 64 + word.trailing_zeros() as usize;
+			}
+			remaining -= count;
+			word_index += 1;
+		}
+	}
+	pub(crate) fn memory_usage(&self) -> usize {
+		self.words.capacity() * size_of::<u64>() + self.block_ranks.capacity() * size_of::<u32>()
+	}
+}
+
+#[derive(Default)]
+pub(crate) struct BitVectorBuilder {
+	words: Vec<u64>,
+	len: usize,
+}
+
+impl BitVectorBuilder {
+	pub(crate) fn push(&mut self, bit: bool) {
+		if self.len.is_multiple_of(64) {
+			self.words.push(0);
+		}
+		if bit {
+			*self.words.last_mut().unwrap() |= 1 << (self.len % 64);
+		}
+		self.len += 1;
+	}
+	pub(crate) fn build(mut self) -> BitVector {
+		self.words.shrink_to_fit();
+		let mut block_ranks = Vec::with_capacity(self.words.len() / WORDS_PER_BLOCK + 2);
+		let mut rank = 0;
+		for block in self.words.chunks(WORDS_PER_BLOCK) {
+			block_ranks.push(rank);
+			rank += block.iter().map(|word| word.count_ones()).sum::<u32>();
+		}
+		block_ranks.push(rank);
+		// an extra word means that queries at the very end don't need bounds checks
+		self.words.push(0);
+		BitVector {
+			words: self.words,
+			len: self.len,
+			block_ranks,
+		}
+	}
+}
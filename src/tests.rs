@@ -1,5 +1,43 @@
-use std::{collections::HashMap, fs, panic};
-use rand::{distr::Alphanumeric, Rng};
+use std::collections::HashMap;
+use rand::{distr::Alphanumeric, rngs::ThreadRng, Rng};
+
+
+
+/// Creates a random key with fewer than `max_len` chars, where every char is from `alphabet`
+fn random_key(rnd: &mut impl Rng, alphabet: &[char], max_len: usize) -> String {
+	let key_len = rnd.random_range(0..max_len);
+	(0..key_len).map(|_| alphabet[rnd.random_range(0..alphabet.len())]).collect()
+}
+
+/// Inserts `iterations` random keys into a StringTree and a HashMap side by side (removing a random earlier key whenever more than `max_keys` have been inserted), validates the tree every `validate_every` iterations, and returns the tree once it's checked against the HashMap
+fn fuzz_string_tree(iterations: usize, max_keys: usize, validate_every: usize, mut make_key: impl FnMut(&mut ThreadRng) -> String) -> crate::StringTree<u64> {
+	let mut hashmap = HashMap::new();
+	let mut string_tree = crate::StringTree::new();
+	let mut all_keys = vec!();
+	let mut rnd = rand::rng();
+	for i in 0..iterations {
+		let key = make_key(&mut rnd);
+		let value = rnd.random::<u64>();
+		all_keys.push(key.to_string());
+		assert_eq!(string_tree.insert(&key, value), hashmap.insert(key, value));
+		if all_keys.len() > max_keys {
+			let key = all_keys.swap_remove(rnd.random_range(..max_keys));
+			let value_tree = string_tree.remove(&key);
+			let value_hashmap = hashmap.remove(&key);
+			assert_eq!(value_hashmap, value_tree);
+		}
+		if i % validate_every == 0 {
+			assert_eq!(string_tree.validate(), Ok(()));
+		}
+	}
+	
+	assert_eq!(string_tree.validate(), Ok(()));
+	for (key, value) in &hashmap {
+		assert_eq!(string_tree.get(key), Some(value));
+	}
+	assert_eq!(string_tree.iter().count(), hashmap.len());
+	string_tree
+}
 
 
 
@@ -42,85 +80,1181 @@ use rand::{distr::Alphanumeric, Rng};
 
 
 
-use serde::{Serialize, Deserialize};
+#[test]
+fn inherited_values() {
+	let string_tree = crate::StringTree::from([("org/", 1), ("org/team/", 2), ("org/team/a", 3), ("org/teamx", 4)]);
+	assert_eq!(string_tree.get_inherited("org/team/a"), Some(("org/team/a", &3)));
+	assert_eq!(string_tree.get_inherited("org/team/b"), Some(("org/team/", &2)));
+	assert_eq!(string_tree.get_inherited("org/teamxyz"), Some(("org/teamx", &4)));
+	assert_eq!(string_tree.get_inherited_with_separator("org/teamxyz", '/'), Some(("org/", &1)));
+	assert_eq!(string_tree.get_inherited("other"), None);
+	// the key only has to live as long as the returned prefix
+	let value = string_tree.get_inherited(&String::from("org/team/b")).map(|(_, value)| value);
+	assert_eq!(value, Some(&2));
+	assert_eq!(string_tree.step("org/team/b").map(|node| node.inherited_value()), None);
+	assert_eq!(string_tree.step("org/team/a").map(|node| node.inherited_value()), Some(Some(&3)));
+	assert_eq!(string_tree.step("org/teamx").map(|node| node.inherited_value_with_separator('/')), Some(Some(&4)));
+	
+	// "/a" is only an ancestor of "/ab" by bytes, not by segments, and there is no other ancestor to fall back to
+	let string_tree = crate::StringTree::from([("/a", 1), ("/abc/d", 2)]);
+	assert_eq!(string_tree.get_inherited("/ab"), Some(("/a", &1)));
+	assert_eq!(string_tree.get_inherited_with_separator("/ab", '/'), None);
+	assert_eq!(string_tree.get_inherited_with_separator("/a/b", '/'), Some(("/a", &1)));
+	assert_eq!(string_tree.get_inherited_with_separator("x/y", '/'), None);
+	assert_eq!(string_tree.step("/abc").map(|node| node.inherited_value()), Some(Some(&1)));
+	assert_eq!(string_tree.step("/abc").map(|node| node.inherited_value_with_separator('/')), Some(None));
+	assert_eq!(string_tree.step("/abc/d").map(|node| node.inherited_value_with_separator('/')), Some(Some(&2)));
+	assert_eq!(string_tree.step("/").map(|node| node.inherited_value_with_separator('/')), Some(None));
+}
+
+
+
+#[test]
+fn segments() {
+	let string_tree = crate::StringTree::from([("usr/bin/env", 1), ("usr/bin/sh", 2), ("usr/lib", 3), ("usrx", 4), ("etc", 5)]);
+	let root = string_tree.segments('/');
+	let child_names = root.child_segments().map(|(name, _)| name).collect::<Vec<_>>();
+	assert_eq!(child_names, vec!("etc", "usr", "usrx"));
+	let bin = root.step_segment("usr").and_then(|usr| usr.step_segment("bin")).unwrap();
+	assert_eq!(bin.segment_depth(), 2);
+	assert_eq!(bin.path(), "usr/bin");
+	let child_names = bin.child_segments().map(|(name, _)| name).collect::<Vec<_>>();
+	assert_eq!(child_names, vec!("env", "sh"));
+	assert_eq!(bin.parent_segment().map(|usr| usr.path()), Some(String::from("usr")));
+	assert_eq!(root.step_segments("usr/lib").and_then(|lib| lib.value()), Some(&3));
+	assert_eq!(string_tree.step("usr/bin/sh").map(|node| node.segment_view('/').segment_depth()), Some(3));
+	
+	// a separator belongs to the segment after it, so "/a/" is the start of an empty segment below "/a"
+	let string_tree = crate::StringTree::from([("/a", 1), ("/a/", 2)]);
+	let a = string_tree.step("/a").unwrap().segment_view('/');
+	let a_slash = string_tree.step("/a/").unwrap().segment_view('/');
+	assert_eq!((a.segment_depth(), a_slash.segment_depth()), (2, 3));
+	assert_eq!(string_tree.segments('/').step_segments("/a/").map(|view| (view.path(), view.segment_depth())), Some((String::from("/a/"), 3)));
+	assert_eq!(a_slash.parent_segment().map(|view| (view.path(), view.segment_depth())), Some((String::from("/a"), 2)));
+	assert_eq!(a.parent_segment().map(|view| (view.path(), view.segment_depth())), Some((String::new(), 1)));
+	assert_eq!(a.child_segments().map(|(name, view)| (name, view.path(), view.segment_depth())).collect::<Vec<_>>(), vec!((String::new(), String::from("/a/"), 3)));
+	assert_eq!(a.step_segment("").map(|view| view.value()), Some(Some(&2)));
+}
+
 
-#[derive(Serialize, Deserialize)]
-struct MyData {
-    map: HashMap<String, u64>,
+
+#[test]
+fn router() {
+	use crate::{Router, RouteError};
+	let mut router = Router::new();
+	router.insert("/users", 1).unwrap();
+	router.insert("/users/me", 2).unwrap();
+	router.insert("/users/:id", 3).unwrap();
+	router.insert("/users/:id/posts/:post", 4).unwrap();
+	router.insert("/files/*rest", 5).unwrap();
+	router.insert("/files/readme", 6).unwrap();
+	assert_eq!(router.insert("/users/:name", 7), Err(RouteError::Conflict {route: String::from("/users/:name"), existing: String::from("/users/:id")}));
+	assert_eq!(router.insert("/a/*rest/b", 8), Err(RouteError::CatchAllNotLast(String::from("/a/*rest/b"))));
+	assert_eq!(router.insert("/a/:", 9), Err(RouteError::MissingParamName(String::from("/a/:"))));
+	assert_eq!(router.len(), 6);
+	
+	assert_eq!(router.at("/users").map(|found| *found.value), Some(1));
+	assert_eq!(router.at("/users/me").map(|found| *found.value), Some(2));
+	let found = router.at("/users/42").unwrap();
+	assert_eq!((*found.value, found.param("id")), (3, Some("42")));
+	let found = router.at("/users/me/posts/7").unwrap();
+	assert_eq!(found.value, &4);
+	assert_eq!(found.params, vec!(("id", "me"), ("post", "7")));
+	assert_eq!(router.at("/files/readme").map(|found| *found.value), Some(6));
+	let found = router.at("/files/docs/guide.md").unwrap();
+	assert_eq!((*found.value, found.param("rest")), (5, Some("docs/guide.md")));
+	assert!(router.at("/users/").is_none());
+	assert!(router.at("/other").is_none());
+	
+	assert_eq!(router.remove("/users/:other"), Some(3));
+	assert!(router.at("/users/42").is_none());
 }
 
-#[allow(static_mut_refs, unused)]
+
+
 #[test]
-fn fuzzing() {
+fn set_operations() {
+	use crate::StringTreeSet;
+	let a = StringTreeSet::from(["apple", "apricot", "banana", "cherry", "x"]);
+	let b = StringTreeSet::from(["apple", "banana", "blueberry", "xyz"]);
+	assert!(a.contains("apricot") && !a.contains("apr"));
+	assert_eq!(a.iter_prefix("ap").collect::<Vec<_>>(), vec!("apple", "apricot"));
+	assert_eq!(a.union(&b).iter().collect::<Vec<_>>(), vec!("apple", "apricot", "banana", "blueberry", "cherry", "x", "xyz"));
+	assert_eq!(a.intersection(&b).iter().collect::<Vec<_>>(), vec!("apple", "banana"));
+	assert_eq!(a.difference(&b).iter().collect::<Vec<_>>(), vec!("apricot", "cherry", "x"));
+	assert_eq!(a.symmetric_difference(&b).iter().collect::<Vec<_>>(), vec!("apricot", "blueberry", "cherry", "x", "xyz"));
+	assert!(a.intersection(&b).is_subset(&a));
+	assert!(!a.is_subset(&b));
+	assert!(a.union(&b).is_superset(&b));
+	assert!(a.intersection(&b).as_tree().step("x").is_none());
+	let mut c = a.clone();
+	assert!(c.remove("x"));
+	assert!(!c.insert("apple"));
+	assert!(c.is_subset(&a));
+	// keys this long would overflow the stack if the trees were walked recursively
+	let long_key = "a".repeat(100000);
+	let long_a = StringTreeSet::from([long_key.as_str(), "b"]);
+	let long_b = StringTreeSet::from([&long_key[..50000], "b"]);
+	let union = long_a.union(&long_b);
+	assert!(union.contains(&long_key) && union.contains(&long_key[..50000]) && union.contains("b"));
+	assert!(long_a.is_subset(&union) && long_b.is_subset(&union));
+	let intersection = long_a.intersection(&long_b);
+	assert_eq!(intersection.iter().collect::<Vec<_>>(), vec!("b"));
+	assert!(intersection.as_tree().step("a").is_none());
+	assert!(long_a.symmetric_difference(&long_b).is_superset(&long_a.difference(&long_b)));
+	// only nodes that lead to a kept key are added, so combined sets are as small as sets built from their keys
+	for combined in [a.union(&b), a.intersection(&b), a.difference(&b), a.symmetric_difference(&b)] {
+		assert!(combined.tree.free_nodes.is_empty());
+		assert_eq!(combined.tree.node_pointers.len(), StringTreeSet::from(combined.iter()).tree.node_pointers.len());
+	}
+	// `()` values only take one bit per node
+	let large = StringTreeSet::from((0..1000).map(|i| format!("key/{i}")));
+	let report = large.as_tree().memory_usage();
+	assert!(report.values < report.nodes / 2);
+}
+
+
+
+#[test]
+fn zero_sized_values() {
+	use std::sync::atomic::{AtomicUsize, Ordering};
+	static DROPS: AtomicUsize = AtomicUsize::new(0);
+	#[derive(Clone)]
+	struct Counted;
+	impl Drop for Counted {
+		fn drop(&mut self) {
+			DROPS.fetch_add(1, Ordering::Relaxed);
+		}
+	}
+	let mut string_tree = crate::StringTree::from([("a", Counted), ("ab", Counted), ("b", Counted)]);
+	assert_eq!(DROPS.load(Ordering::Relaxed), 0);
+	assert!(string_tree.remove("ab").is_some());
+	assert_eq!(DROPS.load(Ordering::Relaxed), 1);
+	assert!(string_tree.insert("a", Counted).is_some());
+	assert_eq!(DROPS.load(Ordering::Relaxed), 2);
+	assert!(string_tree.get("a").is_some() && string_tree.get("ab").is_none());
+	let cloned = string_tree.clone();
+	drop(string_tree);
+	assert_eq!(DROPS.load(Ordering::Relaxed), 4);
+	assert_eq!(cloned.iter().map(|(key, _)| key).collect::<Vec<_>>(), vec!("a", "b"));
+	drop(cloned);
+	assert_eq!(DROPS.load(Ordering::Relaxed), 6);
+}
+
+
+
+#[test]
+fn multi_map() {
+	let mut multi_map = crate::StringTreeMultiMap::from([("rust", 1), ("rust", 4), ("rusty", 2), ("trie", 3)]);
+	multi_map.insert("rust", 7);
+	assert_eq!(multi_map.len(), 5);
+	assert_eq!(multi_map.get_all("rust"), &[1, 4, 7]);
+	assert_eq!(multi_map.get_all("rus"), &[] as &[i32]);
+	assert_eq!(multi_map.iter_prefix("rust").collect::<Vec<_>>(), vec!((String::from("rust"), &[1, 4, 7][..]), (String::from("rusty"), &[2][..])));
+	assert_eq!(multi_map.remove_one("rust", |value| *value > 1), Some(4));
+	assert_eq!(multi_map.remove_one("rusty", |_| true), Some(2));
+	assert!(!multi_map.contains_key("rusty"));
+	assert_eq!(multi_map.remove_all("rust"), vec!(1, 7));
+	assert_eq!(multi_map.len(), 1);
+	assert_eq!(multi_map.iter().map(|(key, _)| key).collect::<Vec<_>>(), vec!("trie"));
+}
+
+
+
+#[test]
+fn merge() {
+	let base = crate::StringTree::from([("log/level", 1), ("log/file", 2), ("port", 3)]);
+	let overrides = crate::StringTree::from([("log/level", 10), ("log/color", 20), ("host", 30)]);
+	
+	let mut merged = base.clone();
+	merged.merge(overrides.clone(), |key, base_value, override_value| {
+		assert_eq!(key, "log/level");
+		base_value + override_value
+	});
+	assert_eq!(merged.iter().map(|(key, value)| (key, *value)).collect::<Vec<_>>(), vec!(
+		(String::from("host"), 30),
+		(String::from("log/color"), 20),
+		(String::from("log/file"), 2),
+		(String::from("log/level"), 11),
+		(String::from("port"), 3),
+	));
+	
+	let mut extended = base.clone();
+	extended.extend_from(overrides.clone());
+	assert_eq!(extended.get("log/level"), Some(&10));
+	assert_eq!(extended.get("log/file"), Some(&2));
+	
+	// filtering happens in place, so the nodes that are kept also keep their ids
+	let mut intersected = base.clone();
+	let level_id = intersected.step("log/level").unwrap().id();
+	let file_id = intersected.step("log/file").unwrap().id();
+	intersected.intersect_with(&overrides);
+	assert_eq!(intersected.iter().map(|(key, value)| (key, *value)).collect::<Vec<_>>(), vec!((String::from("log/level"), 1)));
+	assert!(intersected.step("log/f").is_none());
+	assert_eq!(intersected.node(level_id).and_then(|node| node.value()), Some(&1));
+	assert!(intersected.node(file_id).is_none());
+	
+	let mut difference = base.clone();
+	let port_id = difference.step("port").unwrap().id();
+	let level_id = difference.step("log/level").unwrap().id();
+	difference.difference(&overrides);
+	assert_eq!(difference.iter().map(|(key, _)| key).collect::<Vec<_>>(), vec!("log/file", "port"));
+	assert!(difference.step("log/l").is_none());
+	assert_eq!(difference.node(port_id).and_then(|node| node.value()), Some(&3));
+	assert!(difference.node(level_id).is_none());
+	
+	// keys this long would overflow the stack if the trees were walked recursively
+	let long_key = "a".repeat(100000);
+	let long_base = crate::StringTree::from([(&long_key[..50000], 1), ("b", 2)]);
+	let long_other = crate::StringTree::from([(long_key.as_str(), 10), (&long_key[..50000], 20)]);
+	let mut long_merged = long_base.clone();
+	long_merged.merge(long_other.clone(), |_, base_value, other_value| base_value + other_value);
+	assert_eq!(long_merged.get(&long_key[..50000]), Some(&21));
+	assert_eq!(long_merged.get(&long_key), Some(&10));
+	let mut long_intersected = long_other.clone();
+	long_intersected.intersect_with(&long_base);
+	assert_eq!(long_intersected.get(&long_key[..50000]), Some(&20));
+	assert!(long_intersected.get(&long_key).is_none());
+	let mut long_difference = long_other;
+	long_difference.difference(&long_base);
+	assert_eq!(long_difference.get(&long_key), Some(&10));
+	assert!(long_difference.get(&long_key[..50000]).is_none());
+}
+
+
+
+#[test]
+fn diff_and_patch() {
+	use crate::{Change, Patch};
+	let old = crate::StringTree::from([("a", 1), ("ab", 2), ("abc", 3), ("b", 4)]);
+	let new = crate::StringTree::from([("a", 1), ("ab", 20), ("b", 4), ("ba", 5)]);
+	assert_eq!(old.diff(&new).collect::<Vec<_>>(), vec!(
+		Change::Changed(String::from("ab"), &2, &20),
+		Change::Removed(String::from("abc"), &3),
+		Change::Added(String::from("ba"), &5),
+	));
+	assert_eq!(old.diff(&old).count(), 0);
+	
+	let patch = old.make_patch(&new);
+	let patch = serde_json::from_str::<Patch<i32>>(&serde_json::to_string(&patch).unwrap()).unwrap();
+	let mut patched = old.clone();
+	patched.apply_patch(patch);
+	assert_eq!(patched.diff(&new).count(), 0);
+}
+
+
+
+#[test]
+fn diff_skips_unchanged_subtrees() {
+	use crate::Change;
+	let mut old = crate::StringTree::new();
+	for i in 0..1000 {
+		old.insert(format!("config/a/{i}"), i);
+		old.insert(format!("config/b/{i}"), i);
+	}
+	let mut new = old.clone();
+	assert_eq!(old.diff(&new).count(), 0);
+	new.insert("config/b/x", 1);
+	*new.get_mut("config/b/5").unwrap() = 50;
+	old.remove("config/b/7");
+	
+	// change a value in "config/a/" without going through any of the tree's methods, which is only noticed if the subtree is walked
+	let index = new.descend(0, b"config/a/1").unwrap();
+	new.node_values.replace(index as usize, Some(-1));
+	assert_eq!(old.diff(&new).collect::<Vec<_>>(), vec!(
+		Change::Changed(String::from("config/b/5"), &5, &50),
+		Change::Added(String::from("config/b/7"), &7),
+		Change::Added(String::from("config/b/x"), &1),
+	));
+	
+	// trees that were built separately have no versions in common, so they are fully compared
+	let rebuilt = crate::StringTree::from(new.iter().map(|(key, value)| (key, *value)));
+	assert_eq!(rebuilt.diff(&new).count(), 0);
+	assert_eq!(old.diff(&rebuilt).count(), 4);
+	
+	// changes made after a tree has been cloned again are still found
+	let snapshot = new.clone();
+	*new.get_mut("config/a/2").unwrap() = 20;
+	assert_eq!(snapshot.diff(&new).collect::<Vec<_>>(), vec!(Change::Changed(String::from("config/a/2"), &2, &20)));
+}
+
+
+
+#[test]
+fn persistent_string_tree() {
+	let v1 = crate::PersistentStringTree::from([("route/a", 1), ("route/b", 2), ("héllo", 3)]);
+	let v2 = v1.insert("route/c", 4).insert("route/a", 10);
+	let v3 = v2.remove("route/b").unwrap();
+	assert!(v3.remove("route/x").is_none());
+	assert_eq!((v1.len(), v2.len(), v3.len()), (3, 4, 3));
+	assert_eq!(v1.get("route/a"), Some(&1));
+	assert_eq!(v2.get("route/a"), Some(&10));
+	assert_eq!(v1.get("route/c"), None);
+	assert_eq!(v2.get("route/b"), Some(&2));
+	assert_eq!(v3.get("route/b"), None);
+	assert_eq!(v3.iter_prefix("route/").map(|(key, value)| (key, *value)).collect::<Vec<_>>(), vec!((String::from("route/a"), 10), (String::from("route/c"), 4)));
+	assert_eq!(v1.step("h").unwrap().children().map(|node| node.path()).collect::<Vec<_>>(), vec!("hé"));
+	assert_eq!(v1.step("route/").unwrap().children().map(|node| node.path()).collect::<Vec<_>>(), vec!("route/a", "route/b"));
+	let snapshot = v3.clone();
+	assert!(snapshot.ptr_eq(&v3));
+	assert!(!v1.ptr_eq(&v2));
+	let empty = v1.remove("route/a").and_then(|v| v.remove("route/b")).and_then(|v| v.remove("héllo")).unwrap();
+	assert!(empty.is_empty());
+	assert!(empty.step("r").is_none());
+	
+	// keys this long would overflow the stack if the nodes were copied or dropped recursively
+	let long_key = "a".repeat(100000);
+	let long_v1 = crate::PersistentStringTree::from([(&long_key[..50000], 1)]);
+	let long_v2 = long_v1.insert(&long_key, 2);
+	let long_v3 = long_v2.remove(&long_key[..50000]).unwrap();
+	assert_eq!((long_v2.get(&long_key[..50000]), long_v2.get(&long_key)), (Some(&1), Some(&2)));
+	assert_eq!((long_v3.get(&long_key[..50000]), long_v3.get(&long_key)), (None, Some(&2)));
+	assert!(long_v3.remove(&long_key).unwrap().is_empty());
+	drop(long_v1);
+	drop(long_v2);
+	assert_eq!(long_v3.len(), 1);
+}
+
+
+
+#[test]
+fn concurrent_string_tree() {
+	use std::{sync::Mutex, thread};
+	
+	#[cfg(debug_assertions)]
+	const ITERATIONS: usize = 2000;
+	#[cfg(not(debug_assertions))]
+	const ITERATIONS: usize = 20000;
+	
+	let string_tree = crate::ConcurrentStringTree::new();
+	let hashmap = Mutex::new(HashMap::new());
+	thread::scope(|scope| {
+		for thread_id in 0..8 {
+			let (string_tree, hashmap) = (&string_tree, &hashmap);
+			scope.spawn(move || {
+				// each thread has its own keys so that the model stays in sync, but the random first chars make threads share subtrees
+				let mut rnd = rand::rng();
+				let mut own_keys = vec!();
+				for i in 0..ITERATIONS {
+					let first_char = char::from(rnd.sample(Alphanumeric));
+					let key = format!("{first_char}{thread_id}-{i}");
+					let value = rnd.random::<u64>();
+					assert_eq!(string_tree.insert(&key, value), None);
+					hashmap.lock().unwrap().insert(key.clone(), value);
+					own_keys.push(key);
+					if rnd.random_bool(0.3) {
+						let key = own_keys.swap_remove(rnd.random_range(..own_keys.len()));
+						let value_hashmap = hashmap.lock().unwrap().remove(&key);
+						assert_eq!(string_tree.remove(&key), value_hashmap);
+					}
+					if own_keys.is_empty() {continue;}
+					let key = &own_keys[rnd.random_range(..own_keys.len())];
+					assert_eq!(string_tree.get(key), hashmap.lock().unwrap().get(key).copied());
+				}
+			});
+		}
+		// a reader that runs prefix scans while the writers are running
+		scope.spawn(|| {
+			for _ in 0..20 {
+				let mut prev_key = String::new();
+				string_tree.for_each_prefix("", |key, _| {
+					assert!(*key > *prev_key);
+					prev_key = key.to_string();
+				});
+			}
+		});
+	});
+	
+	let hashmap = hashmap.into_inner().unwrap();
+	assert_eq!(string_tree.len(), hashmap.len());
+	let mut expected = hashmap.into_iter().collect::<Vec<_>>();
+	expected.sort();
+	assert_eq!(string_tree.collect_prefix(""), expected);
+	let string_tree = string_tree.into_tree();
+	assert_eq!(string_tree.iter().map(|(key, value)| (key, *value)).collect::<Vec<_>>(), expected);
+}
+
+
+
+#[test]
+fn concurrent_string_tree_shared_prefix() {
+	use std::{sync::Mutex, thread};
 	
-	//let key = Simd::splat(target_key);
-	//let keys: Simd<u8, _> = Simd::from_array(&);
-	//let mask = key.lanes_eq(keys);
-	
-	//let hashmap = fs::read_to_string("saved_hashmap.json").unwrap();
-	//let hashmap = serde_json::from_str::<MyData>(&hashmap).unwrap().map;
-	//let mut string_tree_2 = crate::StringTree::load_self();
-	//let mut count = 0;
-	//for key in hashmap.keys() {
-	//	count += 1;
-	//	println!("{count}");
-	//	string_tree_2.remove(key);
-	//}
-	
-	//panic::set_hook(Box::new(|_info| {
-	//	unsafe {
-	//		let string_tree_2 = STRING_TREE_2.as_ref().unwrap_unchecked();
-	//		let hashmap = HASHMAP.as_ref().unwrap_unchecked();
-	//		println!("error encountered, checking basic integrity...");
-	//		let mut value_count = 0;
-	//		for (key, value) in hashmap.iter() {
-	//			assert_eq!(Some(value), string_tree_2.get(key));
-	//			value_count += 1;
-	//		}
-	//		let mut nodes = vec!(string_tree_2.root_node());
-	//		while let Some(node) = nodes.pop() {
-	//			for child in node.children() {nodes.push(child);}
-	//			if let Some(value) = node.value() {
-	//				let key = node.path();
-	//				value_count -= 1;
-	//				assert_eq!(Some(value), hashmap.get(&key));
-	//			}
-	//		}
-	//		assert_eq!(value_count, 0);
-	//		println!("done, saving...");
-	//		string_tree_2.save_self();
-	//		let data = serde_json::to_string_pretty(&MyData {map: hashmap.clone()}).unwrap();
-	//		fs::write("saved_hashmap.json", data).unwrap();
-	//		println!("done");
-	//	}
-	//}));
-	
-	//let mut hashmap = HashMap::new();
-	//let mut string_tree = crate::StringTree::new();
-	//let mut all_keys = vec!();
-	
-	//#[cfg(debug_assertions)]
-	//const ITERATIONS: usize = 100000;
-	//#[cfg(not(debug_assertions))]
-	//const ITERATIONS: usize = 10000000;
-	
-	//let mut rnd = rand::rng();
-	//for _ in 0..ITERATIONS {
-	//	let key = (&mut rnd)
-	//		.sample_iter(&Alphanumeric)
-	//		.take(16)
-	//		.map(char::from)
-	//		.collect::<String>();
-	//	let value = rnd.random::<u64>();
-	//	all_keys.push(key.to_string());
-	//	string_tree.insert(&key, value);
-	//	hashmap.insert(key, value);
-	//	if all_keys.len() > 1000 {
-	//		let key = all_keys.swap_remove(rnd.random_range(..1000u64) as usize);
-	//		let value_tree = string_tree.remove(&key);
-	//		let value_hashmap = hashmap.remove(&key);
-	//		assert_eq!(value_hashmap, value_tree);
-	//	}
-	//}
+	#[cfg(debug_assertions)]
+	const ITERATIONS: usize = 300;
+	#[cfg(not(debug_assertions))]
+	const ITERATIONS: usize = 3000;
+	
+	// every key starts with "user/", so the writers and readers all work on keys with a shared prefix
+	let string_tree = crate::ConcurrentStringTree::new();
+	let hashmap = Mutex::new(HashMap::new());
+	thread::scope(|scope| {
+		for thread_id in 0..8 {
+			let (string_tree, hashmap) = (&string_tree, &hashmap);
+			scope.spawn(move || {
+				let mut rnd = rand::rng();
+				let mut own_keys = vec!();
+				for i in 0..ITERATIONS {
+					let key = format!("user/{thread_id}/{i}");
+					let value = rnd.random::<u64>();
+					assert_eq!(string_tree.insert(&key, value), None);
+					hashmap.lock().unwrap().insert(key.clone(), value);
+					own_keys.push(key);
+					if rnd.random_bool(0.3) {
+						let key = own_keys.swap_remove(rnd.random_range(..own_keys.len()));
+						let value_hashmap = hashmap.lock().unwrap().remove(&key);
+						assert_eq!(string_tree.remove(&key), value_hashmap);
+					}
+					if own_keys.is_empty() {continue;}
+					let key = &own_keys[rnd.random_range(..own_keys.len())];
+					assert_eq!(string_tree.get_mut_with(key, |value| *value), hashmap.lock().unwrap().get(key).copied());
+				}
+			});
+		}
+		// readers for both short prefixes (which merge every subtree) and long prefixes (which only need one subtree)
+		for prefix in ["user/", "user/3/1"] {
+			let string_tree = &string_tree;
+			scope.spawn(move || {
+				for _ in 0..5 {
+					let mut prev_key = String::new();
+					string_tree.for_each_prefix(prefix, |key, _| {
+						assert!(key.starts_with(prefix));
+						assert!(*key > *prev_key);
+						prev_key = key.to_string();
+					});
+				}
+			});
+		}
+	});
+	
+	let hashmap = hashmap.into_inner().unwrap();
+	assert_eq!(string_tree.len(), hashmap.len());
+	let mut expected = hashmap.into_iter().collect::<Vec<_>>();
+	expected.sort();
+	assert_eq!(string_tree.collect_prefix("user/"), expected);
+	let expected_long = expected.iter().filter(|(key, _)| key.starts_with("user/3/1")).cloned().collect::<Vec<_>>();
+	assert_eq!(string_tree.collect_prefix("user/3/1"), expected_long);
+	assert_eq!(string_tree.into_tree().iter().map(|(key, value)| (key, *value)).collect::<Vec<_>>(), expected);
+}
+
+
+
+#[test]
+fn concurrent_string_tree_shard_index() {
+	use std::collections::HashSet;
+	type Tree = crate::ConcurrentStringTree<()>;
+	// keys that only share a short prefix are spread over many subtrees, while keys that share their first 8 bytes always share a subtree
+	let shard_indices = (0..1000).map(|i| Tree::shard_index(&format!("user/{i}"))).collect::<HashSet<_>>();
+	assert!(shard_indices.len() > 128, "only {} subtrees were used", shard_indices.len());
+	assert_eq!(Tree::shard_index("user/123/a"), Tree::shard_index("user/123/b"));
+	assert_eq!(Tree::shard_index("user/123"), Tree::shard_index("user/1234"));
+}
+
+
+
+#[cfg(feature = "rayon")]
+#[test]
+fn parallel() {
+	use rayon::prelude::*;
+	let mut rnd = rand::rng();
+	let pairs = (0..10000).map(|i| {
+		let key_len = rnd.random_range(1..8);
+		let key = (&mut rnd).sample_iter(&Alphanumeric).take(key_len).map(char::from).collect::<String>();
+		(key, i)
+	}).collect::<HashMap<_, _>>();
+	
+	let mut string_tree = crate::StringTree::par_from(pairs.clone().into_par_iter());
+	let mut expected = pairs.iter().map(|(key, value)| (key.clone(), *value)).collect::<Vec<_>>();
+	expected.sort();
+	assert_eq!(string_tree.par_iter().map(|(key, value)| (key, *value)).collect::<Vec<_>>(), expected);
+	assert_eq!(string_tree.iter().map(|(key, value)| (key, *value)).collect::<Vec<_>>(), expected);
+	let expected_prefix = expected.iter().filter(|(key, _)| key.starts_with('a')).cloned().collect::<Vec<_>>();
+	assert_eq!(string_tree.par_iter_prefix("a").map(|(key, value)| (key, *value)).collect::<Vec<_>>(), expected_prefix);
+	
+	string_tree.par_values_mut().for_each(|value| *value *= 2);
+	assert!(pairs.iter().all(|(key, value)| string_tree.get(key) == Some(&(value * 2))));
+	string_tree.par_extend(vec!((String::from(""), 0), (String::from("new"), 1)));
+	assert_eq!(string_tree.get(""), Some(&0));
+	assert_eq!(string_tree.get("new"), Some(&1));
+	
+	// groups with a new first byte are appended as a block, which has to keep the existing nodes and free slots intact
+	let mut string_tree = crate::StringTree::from([("avocado", 2), ("apple", 1)]);
+	string_tree.remove("avocado");
+	let id = string_tree.step("apple").unwrap().id();
+	string_tree.par_extend(vec!(("banana", 3), ("apricot", 4), ("é", 5)));
+	assert_eq!(string_tree.validate(), Ok(()));
+	assert_eq!(string_tree.iter().map(|(key, value)| (key, *value)).collect::<Vec<_>>(), vec!(
+		(String::from("apple"), 1),
+		(String::from("apricot"), 4),
+		(String::from("banana"), 3),
+		(String::from("é"), 5),
+	));
+	assert_eq!(string_tree.node(id).and_then(|node| node.value()), Some(&1));
+}
+
+
+
+#[test]
+fn from_sorted_iter() {
+	use crate::{StringTree, FromSortedError};
+	let string_tree = StringTree::from_sorted_iter([("", 0), ("a", 1), ("ab", 2), ("abc", 3), ("b", 4), ("été", 5)]).unwrap();
+	assert_eq!(string_tree.iter().map(|(key, value)| (key, *value)).collect::<Vec<_>>(), vec!(
+		(String::from(""), 0),
+		(String::from("a"), 1),
+		(String::from("ab"), 2),
+		(String::from("abc"), 3),
+		(String::from("b"), 4),
+		(String::from("été"), 5),
+	));
+	assert_eq!(string_tree.get("abc"), Some(&3));
+	assert_eq!(string_tree.node_pointers.len(), 1 + 3 + 1 + 5);
+	assert_eq!(StringTree::from_sorted_iter([("a", 1), ("c", 2), ("b", 3)]).err(), Some(FromSortedError::Unsorted {index: 2, key: String::from("b")}));
+	assert_eq!(StringTree::from_sorted_iter([("a", 1), ("a", 2)]).err(), Some(FromSortedError::Duplicate {index: 1, key: String::from("a")}));
+}
+
+
+
+#[test]
+fn memory_usage() {
+	use std::mem::size_of;
+	let mut string_tree = crate::StringTree::<u64>::with_capacity(100);
+	assert!(string_tree.memory_usage().values >= 100 * size_of::<Option<u64>>());
+	for i in 0..20 {
+		string_tree.insert(format!("{}", (b'a' + i) as char), i as u64);
+	}
+	let report = string_tree.memory_usage();
+	assert_eq!(report.nodes, 21);
+	// the root node has 20 children, so its children list is on the heap
+	assert!(report.pointers >= 100 * size_of::<smallvec::SmallVec<[(u8, u32); 4]>>() + 20 * size_of::<(u8, u32)>());
+	string_tree.shrink_to_fit();
+	let report = string_tree.memory_usage();
+	assert_eq!(report.values, 21 * size_of::<Option<u64>>());
+	assert_eq!(report.total(), report.pointers + report.fill_counts + report.stubs + report.parents + report.values + report.generations + report.versions);
+}
+
+
+
+#[test]
+fn compact() {
+	use crate::CompactOrder;
+	let mut string_tree = crate::StringTree::new();
+	let keys = ["delta", "alpha", "charlie", "alphabet", "bravo", "echo", "alps", "dell"];
+	for (i, key) in keys.iter().enumerate() {
+		string_tree.insert(key, i);
+	}
+	string_tree.remove("alphabet");
+	string_tree.remove("bravo");
+	let expected = string_tree.iter().map(|(key, value)| (key, *value)).collect::<Vec<_>>();
+	
+	assert!(string_tree.compact(CompactOrder::DepthFirst) > 0);
+	assert_eq!(string_tree.compact(CompactOrder::DepthFirst), 0);
+	assert_eq!(string_tree.iter().map(|(key, value)| (key, *value)).collect::<Vec<_>>(), expected);
+	// parents always come before their children, and the first key's nodes come first
+	for index in 1..string_tree.node_parents.len() {
+		assert!((string_tree.node_parents[index].0 as usize) < index);
+	}
+	assert_eq!(string_tree.node_parents[1], (0, b'a'));
+	assert_eq!(string_tree.node_parents[2], (1, b'l'));
+	
+	assert!(string_tree.compact(CompactOrder::BreadthFirst) > 0);
+	assert_eq!(string_tree.iter().map(|(key, value)| (key, *value)).collect::<Vec<_>>(), expected);
+	let depth_of = |mut index: usize| {
+		let mut depth = 0;
+		while index != 0 {
+			index = string_tree.node_parents[index].0 as usize;
+			depth += 1;
+		}
+		depth
+	};
+	for index in 1..string_tree.node_parents.len() {
+		assert!(depth_of(index - 1) <= depth_of(index));
+	}
+}
+
+
+
+#[test]
+fn node_ids() {
+	let mut string_tree = crate::StringTree::new();
+	string_tree.insert("apple", 1);
+	string_tree.insert("banana", 2);
+	string_tree.insert("cherry", 3);
+	let apple_id = string_tree.step("apple").unwrap().id();
+	let banana_id = string_tree.step("banana").unwrap().id();
+	let cherry_id = string_tree.step("cherry").unwrap().id();
+	
+	// removing a node in the middle of the node vecs doesn't move any other nodes
+	assert_eq!(string_tree.remove("banana"), Some(2));
+	assert!(string_tree.node(banana_id).is_none());
+	assert_eq!(string_tree.node(apple_id).and_then(|node| node.value()), Some(&1));
+	assert_eq!(string_tree.node(cherry_id).map(|node| node.path()), Some(String::from("cherry")));
+	
+	// new nodes reuse the removed slots, but old ids for those slots stay dead
+	string_tree.insert("blueberry", 4);
+	assert!(string_tree.node(banana_id).is_none());
+	let blueberry_id = string_tree.step("blueberry").unwrap().id();
+	*string_tree.node_mut(blueberry_id).unwrap().value().unwrap() += 10;
+	assert_eq!(string_tree.get("blueberry"), Some(&14));
+	assert_eq!(string_tree.get("cherry"), Some(&3));
+	
+	string_tree.compact(crate::CompactOrder::DepthFirst);
+	assert!(string_tree.node(banana_id).is_none());
+	for id in [apple_id, blueberry_id, cherry_id] {
+		assert!(string_tree.node(id).is_none_or(|node| node.value().is_some()));
+	}
+	let apple_id = string_tree.step("apple").unwrap().id();
+	assert_eq!(string_tree.node(apple_id).and_then(|node| node.value()), Some(&1));
+}
+
+
+
+#[test]
+fn validate() {
+	// short keys from a small alphabet share a lot of nodes, so removals often leave nodes that have to be cleaned up
+	let mut string_tree = fuzz_string_tree(2000, 50, 100, |rnd| random_key(rnd, &['a', 'b', 'c', 'd'], 6));
+	string_tree.compact(crate::CompactOrder::BreadthFirst);
+	assert_eq!(string_tree.validate(), Ok(()));
+	
+	// corrupted trees report the specific broken invariant
+	let mut string_tree = crate::StringTree::new();
+	string_tree.insert("ab", 1);
+	string_tree.insert("ac", 2);
+	let a_index = string_tree.step("a").unwrap().index;
+	let mut corrupted = string_tree.clone();
+	corrupted.node_fill_counts[a_index as usize] = 1;
+	assert_eq!(corrupted.validate(), Err(vec!(crate::IntegrityError::FillCountMismatch {node: a_index, fill_count: 1, children: 2})));
+	let mut corrupted = string_tree.clone();
+	let b_index = string_tree.step("ab").unwrap().index;
+	corrupted.node_parents[b_index as usize].1 = b'x';
+	assert_eq!(corrupted.validate(), Err(vec!(crate::IntegrityError::ParentMismatch {node: a_index, byte: b'b', child: b_index, child_parent: (a_index, b'x')})));
+	let mut corrupted = string_tree.clone();
+	corrupted.node_pointers[a_index as usize].retain(|(byte, _)| *byte != b'b');
+	corrupted.node_fill_counts[a_index as usize] = 1;
+	assert_eq!(corrupted.validate(), Err(vec!(crate::IntegrityError::UnreachableNode(b_index))));
+	let mut corrupted = string_tree.clone();
+	corrupted.node_pointers[a_index as usize].clear();
+	corrupted.node_fill_counts[a_index as usize] = 0;
+	corrupted.free_nodes.push(b_index);
+	let errors = corrupted.validate().unwrap_err();
+	assert!(errors.contains(&crate::IntegrityError::ValuelessLeaf(a_index)));
+	let mut corrupted = string_tree.clone();
+	corrupted.node_stubs.pop();
+	assert_eq!(corrupted.validate(), Err(vec!(crate::IntegrityError::LengthMismatch {pointers: 4, fill_counts: 4, stubs: 3, parents: 4, values: 4, generations: 4, versions: 4})));
+	let mut corrupted = string_tree.clone();
+	corrupted.node_pointers[a_index as usize].push((b'z', 100));
+	corrupted.node_fill_counts[a_index as usize] = 3;
+	assert_eq!(corrupted.validate(), Err(vec!(crate::IntegrityError::ChildOutOfBounds {node: a_index, byte: b'z', child: 100})));
+	let mut corrupted = string_tree.clone();
+	let c_index = string_tree.step("ac").unwrap().index;
+	corrupted.node_pointers[a_index as usize].push((b'b', c_index));
+	corrupted.node_fill_counts[a_index as usize] = 3;
+	assert_eq!(corrupted.validate(), Err(vec!(
+		crate::IntegrityError::DuplicateChildByte {node: a_index, byte: b'b'},
+		crate::IntegrityError::ParentMismatch {node: a_index, byte: b'b', child: c_index, child_parent: (a_index, b'c')},
+		crate::IntegrityError::MultipleParents(c_index),
+	)));
+	let mut corrupted = string_tree.clone();
+	corrupted.free_nodes.push(a_index);
+	assert_eq!(corrupted.validate(), Err(vec!(crate::IntegrityError::FreeNodeInUse(a_index))));
+	let mut corrupted = string_tree.clone();
+	corrupted.node_pointers[a_index as usize].retain(|(byte, _)| *byte != b'b');
+	corrupted.node_pointers[a_index as usize].push((0xff, b_index));
+	corrupted.node_parents[b_index as usize].1 = 0xff;
+	assert_eq!(corrupted.validate(), Err(vec!(crate::IntegrityError::InvalidUtf8Path(b_index))));
+}
+
+
+
+#[test]
+fn byte_string_tree() {
+	let mut byte_tree = crate::ByteStringTree::new();
+	byte_tree.insert(b"caf\xe9", 1); // latin-1
+	byte_tree.insert([0xde, 0xad, 0xbe, 0xef], 2);
+	byte_tree.insert("caf\u{e9}", 3);
+	byte_tree.insert(b"cafe", 4);
+	assert_eq!(byte_tree.get(b"caf\xe9"), Some(&1));
+	assert_eq!(byte_tree.get([0xde, 0xad, 0xbe, 0xef]), Some(&2));
+	assert_eq!(byte_tree.get([0xde, 0xad]), None);
+	assert_eq!(byte_tree.validate(), Ok(()));
+	assert_eq!(
+		byte_tree.iter_prefix(b"caf").collect::<Vec<_>>(),
+		vec!((b"cafe".to_vec(), &4), (b"caf\xc3\xa9".to_vec(), &3), (b"caf\xe9".to_vec(), &1)),
+	);
+	
+	// byte-level children stop in the middle of multi-byte chars
+	let caf_node = byte_tree.step(b"caf").unwrap();
+	let children = caf_node.children_bytes().map(|(byte, node)| (byte, node.path_bytes())).collect::<Vec<_>>();
+	assert_eq!(children, vec!((b'e', b"cafe".to_vec()), (0xc3, b"caf\xc3".to_vec()), (0xe9, b"caf\xe9".to_vec())));
+	assert_eq!(caf_node.step([0xc3, 0xa9]).and_then(|node| node.value()), Some(&3));
+	
+	// conversions only succeed when every key is valid UTF-8
+	let Err(error) = byte_tree.into_string_tree() else {panic!("conversion should fail");};
+	assert_eq!(error.key(), b"caf\xe9");
+	let mut byte_tree = error.into_tree();
+	assert_eq!(byte_tree.remove(b"caf\xe9"), Some(1));
+	assert_eq!(byte_tree.remove([0xde, 0xad, 0xbe, 0xef]), Some(2));
+	let string_tree = crate::StringTree::try_from(byte_tree).unwrap();
+	assert_eq!(string_tree.get("caf\u{e9}"), Some(&3));
+	assert_eq!(string_tree.step("caf\u{e9}").unwrap().path_bytes(), "caf\u{e9}".as_bytes());
+	let byte_tree: crate::ByteStringTree<_> = string_tree.into();
+	assert_eq!(byte_tree.iter().count(), 2);
+}
+
+
+
+#[test]
+fn sequence_tree() {
+	// n-gram counts over token ids
+	let tokens = [3u32, 70000, 3, 70000, 5, 3, 70000, 5];
+	let mut ngram_counts = crate::SequenceTree::<u32, usize>::new();
+	for n in 1..=3 {
+		for ngram in tokens.windows(n) {
+			let count = ngram_counts.get(ngram).copied().unwrap_or(0);
+			ngram_counts.insert(ngram, count + 1);
+		}
+	}
+	assert_eq!(ngram_counts.get([3, 70000]), Some(&3));
+	assert_eq!(ngram_counts.get([3, 70000, 5]), Some(&2));
+	assert_eq!(ngram_counts.get([5, 5]), None);
+	assert_eq!(
+		ngram_counts.iter_prefix([70000]).collect::<Vec<_>>(),
+		vec!((vec!(70000), &3), (vec!(70000, 3), &1), (vec!(70000, 3, 70000), &1), (vec!(70000, 5), &2), (vec!(70000, 5, 3), &1)),
+	);
+	
+	// node traversal
+	let node = ngram_counts.step([3]).unwrap();
+	assert_eq!(node.children().map(|(symbol, _)| symbol).collect::<Vec<_>>(), vec!(70000));
+	let node = node.step([70000]).unwrap();
+	assert_eq!(node.path(), vec!(3, 70000));
+	assert_eq!(node.iter().count(), 3);
+	let id = node.id();
+	assert_eq!(ngram_counts.remove([3, 70000, 5]), Some(2));
+	assert_eq!(ngram_counts.node(id).and_then(|node| node.value()), Some(&3));
+	assert_eq!(ngram_counts.remove([3, 70000]), Some(3));
+	assert_eq!(ngram_counts.node(id).map(|node| node.value()), Some(None));
+	assert_eq!(ngram_counts.remove([3, 70000, 3]), Some(1));
+	assert!(ngram_counts.node(id).is_none());
+	assert_eq!(ngram_counts.validate(), Ok(()));
+	
+	// custom symbols
+	#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Debug)]
+	enum Step {#[default] Left, Right}
+	impl crate::Symbol for Step {}
+	let tree = crate::SequenceTree::<Step, char>::from([(vec!(Step::Left, Step::Right), 'a'), (vec!(Step::Right), 'b')]);
+	assert_eq!(tree.get([Step::Left, Step::Right]), Some(&'a'));
+	assert_eq!(tree.iter().map(|(_, value)| *value).collect::<String>(), "ab");
+}
+
+
+
+#[test]
+fn normalized_keys() {
+	let mut headers = crate::StringTree::with_normalizer(crate::AsciiCaseFold);
+	assert_eq!(headers.insert("Content-Type", "text/html"), None);
+	assert_eq!(headers.insert("content-type", "text/plain"), Some("text/html"));
+	headers.insert("Content-Length", "42");
+	headers.insert("X-Request-Id", "abc");
+	assert_eq!(headers.get("CONTENT-TYPE"), Some(&"text/plain"));
+	assert_eq!(headers.get_key_value("content-type"), Some(("Content-Type", &"text/plain")));
+	assert_eq!(headers.iter().collect::<Vec<_>>(), vec!(("Content-Length", &"42"), ("Content-Type", &"text/plain"), ("X-Request-Id", &"abc")));
+	assert_eq!(headers.iter_prefix("CONTENT-").count(), 2);
+	assert_eq!(headers.step("x-REQUEST").map(|node| node.path()), Some(String::from("x-request")));
+	assert_eq!(headers.remove_entry("x-request-id"), Some((String::from("X-Request-Id"), "abc")));
+	assert!(!headers.contains_key("X-Request-Id"));
+	
+	// custom normalizers can be closures
+	let mut paths = crate::NormalizedStringTree::new(|key: &str| key.trim_end_matches('/').to_string());
+	paths.insert("/users/", 1);
+	assert_eq!(paths.get("/users"), Some(&1));
+	
+	#[cfg(feature = "unicode")]
+	{
+		let mut words = crate::StringTree::with_normalizer(crate::UnicodeCaseFold);
+		words.insert("Straße", 1);
+		assert_eq!(words.get("STRASSE"), Some(&1));
+		assert_eq!(words.iter().next(), Some(("Straße", &1)));
+		let mut names = crate::StringTree::with_normalizer(crate::Nfc);
+		names.insert("caf\u{e9}", 1);
+		assert_eq!(names.get("cafe\u{301}"), Some(&1));
+		let mut names = crate::StringTree::with_normalizer(crate::Nfkc);
+		names.insert("\u{fb01}le", 2);
+		assert_eq!(names.get("file"), Some(&2));
+	}
+}
+
+
+
+#[cfg(feature = "unicode")]
+#[test]
+fn graphemes() {
+	let string_tree = crate::StringTree::from([
+		("\u{1f44d}", 1), // thumbs up
+		("\u{1f44d}\u{1f3fd}", 2), // thumbs up with a skin tone modifier
+		("\u{1f44d}\u{1f3fd}!", 3),
+		("e\u{301}t\u{e9}", 4), // e + combining accent
+		("ab", 5),
+	]);
+	let root = string_tree.root_node();
+	let graphemes = root.grapheme_children().map(|(grapheme, node)| (grapheme, node.path())).collect::<Vec<_>>();
+	assert_eq!(graphemes, vec!(
+		(String::from("a"), String::from("a")),
+		(String::from("e\u{301}"), String::from("e\u{301}")),
+		(String::from("\u{1f44d}"), String::from("\u{1f44d}")),
+		(String::from("\u{1f44d}\u{1f3fd}"), String::from("\u{1f44d}\u{1f3fd}")),
+	));
+	// plain children() splits the modifier and the accent into separate chars
+	assert_eq!(root.step("e").unwrap().children().count(), 1);
+	
+	let thumbs_up = root.step_grapheme("\u{1f44d}\u{1f3fd}").unwrap();
+	assert_eq!(thumbs_up.value(), Some(&2));
+	assert_eq!(thumbs_up.grapheme_children().map(|(grapheme, _)| grapheme).collect::<Vec<_>>(), vec!(String::from("!")));
+	assert!(root.step_grapheme("e").is_none()); // "e" is always followed by the accent
+	assert!(root.step_grapheme("ab").is_none()); // not a single grapheme
+	let e_accent = root.step_grapheme("e\u{301}").unwrap();
+	assert_eq!(e_accent.value(), None);
+	assert_eq!(e_accent.step_grapheme("t").and_then(|node| node.step_grapheme("\u{e9}")).and_then(|node| node.value()), Some(&4));
+}
+
+
+
+#[cfg(feature = "ipnet")]
+#[test]
+fn ip_prefix_tree() {
+	use ipnet::IpNet;
+	use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+	// addresses are kept in a small range so that prefixes overlap often
+	let random_addr = |rnd: &mut rand::rngs::ThreadRng, is_v6: bool| -> IpAddr {
+		if is_v6 {
+			IpAddr::V6(Ipv6Addr::from((rnd.random_range(0..16u128) << 124) | rnd.random_range(0..256u128)))
+		} else {
+			IpAddr::V4(Ipv4Addr::from((rnd.random_range(0..4u32) << 30) | rnd.random_range(0..256u32)))
+		}
+	};
+	let mut prefix_tree = crate::IpPrefixTree::new();
+	let mut all_prefixes: Vec<(IpNet, usize)> = vec!();
+	let mut rnd = rand::rng();
+	for i in 0..500 {
+		let is_v6 = rnd.random_bool(0.3);
+		let max_len = if is_v6 {128} else {32};
+		let prefix = IpNet::new(random_addr(&mut rnd, is_v6), rnd.random_range(0..=max_len)).unwrap().trunc();
+		let expected = all_prefixes.iter().position(|(existing, _)| *existing == prefix).map(|position| all_prefixes.remove(position).1);
+		assert_eq!(prefix_tree.insert(prefix, i), expected);
+		all_prefixes.push((prefix, i));
+	}
+	for _ in 0..100 {
+		let (prefix, value) = all_prefixes.swap_remove(rnd.random_range(..all_prefixes.len()));
+		assert_eq!(prefix_tree.remove(prefix), Some(value));
+	}
+	assert_eq!(prefix_tree.len(), all_prefixes.len());
+	
+	// compare against a linear scan
+	let sorted = |mut items: Vec<(IpNet, usize)>| {items.sort(); items};
+	for _ in 0..500 {
+		let is_v6 = rnd.random_bool(0.3);
+		let addr = random_addr(&mut rnd, is_v6);
+		let expected = all_prefixes.iter().filter(|(prefix, _)| prefix.contains(&addr)).max_by_key(|(prefix, _)| prefix.prefix_len()).copied();
+		assert_eq!(prefix_tree.longest_match(addr).map(|(prefix, value)| (prefix, *value)), expected);
+		
+		let query = IpNet::new(addr, rnd.random_range(0..=if is_v6 {128} else {32})).unwrap().trunc();
+		assert_eq!(prefix_tree.get(query), all_prefixes.iter().find(|(prefix, _)| *prefix == query).map(|(_, value)| value));
+		let covering = prefix_tree.covering(query).map(|(prefix, value)| (prefix, *value)).collect::<Vec<_>>();
+		assert!(covering.is_sorted_by_key(|(prefix, _)| prefix.prefix_len()));
+		assert_eq!(sorted(covering), sorted(all_prefixes.iter().filter(|(prefix, _)| prefix.contains(&query)).copied().collect()));
+		let covered_by = prefix_tree.covered_by(query).map(|(prefix, value)| (prefix, *value)).collect::<Vec<_>>();
+		assert_eq!(covered_by, sorted(all_prefixes.iter().filter(|(prefix, _)| query.contains(prefix)).copied().collect()));
+	}
+	assert_eq!(prefix_tree.iter().count(), all_prefixes.len());
+}
+
+
+
+#[test]
+fn frozen_string_tree() {
+	let mut rnd = rand::rng();
+	let mut string_tree = crate::StringTree::new();
+	for i in 0..2000 {
+		let key = random_key(&mut rnd, &['a', 'b', 'c', '\u{e9}', '\u{1f600}'], 8);
+		string_tree.insert(key, i);
+	}
+	let frozen = string_tree.clone().freeze();
+	assert_eq!(frozen.len(), string_tree.iter().count());
+	assert_eq!(frozen.iter().collect::<Vec<_>>(), string_tree.iter().collect::<Vec<_>>());
+	assert_eq!(frozen.iter_prefix("a\u{e9}").collect::<Vec<_>>(), string_tree.iter_prefix("a\u{e9}").collect::<Vec<_>>());
+	for _ in 0..500 {
+		let key = random_key(&mut rnd, &['a', 'b', 'c', 'd', '\u{e9}', '\u{1f600}'], 10);
+		assert_eq!(frozen.get(&key), string_tree.get(&key));
+		assert_eq!(frozen.longest_match(&key), string_tree.get_inherited(&key));
+		if let Some(node) = frozen.step(&key) {
+			assert_eq!(node.path(), key);
+			let children = node.children().map(|child| child.path()).collect::<Vec<_>>();
+			let mut expected = string_tree.iter_prefix(&key)
+				.filter_map(|(child_key, _)| child_key[key.len()..].chars().next().map(|char| format!("{key}{char}")))
+				.collect::<Vec<_>>();
+			expected.dedup();
+			assert_eq!(children, expected);
+		} else {
+			assert!(string_tree.iter_prefix(&key).next().is_none());
+		}
+	}
+	
+	let thawed = frozen.thaw();
+	assert_eq!(thawed.validate(), Ok(()));
+	assert_eq!(thawed.iter().collect::<Vec<_>>(), string_tree.iter().collect::<Vec<_>>());
+	
+	// only the bytes between each state's smallest and largest child are checked
+	let small = crate::StringTree::from([("b", 1), ("d", 2), ("", 3)]).freeze();
+	assert_eq!(small.child_bytes[0], (b'b', b'd'));
+	assert_eq!(small.child_states(0).collect::<Vec<_>>(), vec!((b'b', small.step("b").unwrap().state), (b'd', small.step("d").unwrap().state)));
+	assert_eq!(small.get(""), Some(&3));
+	
+	// a tree with over 100k nodes, where finding bases must not take quadratic time
+	let mut keys = (0..20000).map(|_| {
+		let key_len = rnd.random_range(4..=16);
+		(&mut rnd).sample_iter(&Alphanumeric).take(key_len).map(char::from).collect::<String>()
+	}).collect::<Vec<_>>();
+	keys.sort();
+	keys.dedup();
+	let large = crate::StringTree::from_sorted_iter(keys.iter().enumerate().map(|(i, key)| (key, i))).unwrap();
+	assert!(large.memory_usage().nodes >= 100000);
+	let frozen = large.freeze();
+	assert_eq!(frozen.len(), keys.len());
+	for (i, key) in keys.iter().enumerate().step_by(97) {
+		assert_eq!(frozen.get(key), Some(&i));
+	}
+}
+
+
+
+#[test]
+fn succinct_string_tree() {
+	let mut rnd = rand::rng();
+	let mut string_tree = crate::StringTree::new();
+	for i in 0..300u32 {
+		let key = random_key(&mut rnd, &['a', 'b', 'c', 'd', 'e', '\u{e9}', '\u{1f600}'], 12);
+		string_tree.insert(key, i);
+	}
+	let succinct = string_tree.clone().into_succinct();
+	assert_eq!(succinct.len(), string_tree.iter().count());
+	assert_eq!(succinct.iter().collect::<Vec<_>>(), string_tree.iter().collect::<Vec<_>>());
+	assert_eq!(succinct.iter_prefix("b\u{1f600}").collect::<Vec<_>>(), string_tree.iter_prefix("b\u{1f600}").collect::<Vec<_>>());
+	assert_eq!(succinct.iter_prefix("zzz").count(), 0);
+	for _ in 0..300 {
+		let key = random_key(&mut rnd, &['a', 'b', 'c', 'd', 'e', 'f', '\u{e9}', '\u{1f600}'], 14);
+		assert_eq!(succinct.get(&key), string_tree.get(&key));
+		assert_eq!(succinct.contains_key(&key), string_tree.get(&key).is_some());
+		if let Some(node) = succinct.step(&key) {
+			assert_eq!(node.path(), key);
+			assert_eq!(node.iter().next(), string_tree.iter_prefix(&key).next());
+		} else {
+			assert!(string_tree.step(&key).is_none());
+		}
+	}
+	
+	// every two-letter key gives about 700 nodes, so both bit vectors cross the 512-bit block boundaries
+	let letters = 'a'..='z';
+	let two_letter_keys = letters.clone().flat_map(|first| letters.clone().map(move |second| format!("{first}{second}"))).collect::<Vec<_>>();
+	let two_letter_tree = crate::StringTree::from(two_letter_keys.iter().enumerate().map(|(i, key)| (key, i)));
+	let two_letter_succinct = two_letter_tree.clone().into_succinct();
+	assert_eq!(two_letter_succinct.iter().collect::<Vec<_>>(), two_letter_tree.iter().collect::<Vec<_>>());
+	for (i, key) in two_letter_keys.iter().enumerate() {
+		assert_eq!(two_letter_succinct.get(key), Some(&i));
+		assert_eq!(two_letter_succinct.step(key).map(|node| node.path()), Some(key.clone()));
+		assert_eq!(two_letter_succinct.get(format!("{key}a")), None);
+	}
+	
+	// rank and select on both sides of the block boundaries
+	for len in [0, 1, 63, 64, 511, 512, 513, 1023, 1024, 1025, 1600] {
+		let bits = (0..len).map(|i| i % 3 == 0 || (500..530).contains(&i)).collect::<Vec<_>>();
+		let mut builder = crate::succinct_string_tree::BitVectorBuilder::default();
+		for &bit in &bits {
+			builder.push(bit);
+		}
+		let bit_vector = builder.build();
+		let (mut ones, mut zeros) = (0, 0);
+		for (i, &bit) in bits.iter().enumerate() {
+			assert_eq!(bit_vector.get(i), bit);
+			assert_eq!(bit_vector.rank1(i), ones);
+			assert_eq!(bit_vector.rank0(i), zeros);
+			if bit {
+				assert_eq!(bit_vector.select1(ones), i);
+				ones += 1;
+			} else {
+				assert_eq!(bit_vector.select0(zeros), i);
+				zeros += 1;
+			}
+		}
+		assert_eq!(bit_vector.rank1(len), ones);
+	}
+	
+	let succinct_memory = succinct.memory_usage();
+	let string_tree_memory = string_tree.memory_usage().total();
+	assert!(succinct_memory * 4 < string_tree_memory);
+	assert!(succinct_memory < string_tree.node_values.len() * 2 + succinct.len() * size_of::<u32>());
+	
+	let empty = crate::StringTree::<u32>::new().into_succinct();
+	assert!(empty.is_empty());
+	assert_eq!(empty.get(""), None);
+	assert_eq!(empty.iter().count(), 0);
+}
+
+
+
+#[test]
+fn dawg() {
+	let mut rnd = rand::rng();
+	let stems = ["walk", "talk", "jump", "climb", "read", "paint", "\u{e9}crire", "\u{1f600}smil"];
+	let suffixes = ["", "s", "ed", "ing", "er", "ers", "ation", "ations"];
+	let mut set = crate::StringTreeSet::new();
+	for stem in stems {
+		for suffix in suffixes {
+			if rnd.random_bool(0.8) {
+				set.insert(format!("{stem}{suffix}"));
+			}
+		}
+	}
+	for _ in 0..300 {
+		let key = random_key(&mut rnd, &['a', 'b', 'c', '\u{e9}'], 8);
+		set.insert(key);
+	}
+	let dawg = set.minimize();
+	assert_eq!(dawg.len(), set.iter().count());
+	assert!(dawg.state_count() * 2 < set.as_tree().node_values.len());
+	assert_eq!(dawg.iter().collect::<Vec<_>>(), set.iter().collect::<Vec<_>>());
+	for prefix in ["walk", "\u{e9}", "ab", "zzz", ""] {
+		assert_eq!(dawg.iter_prefix(prefix).collect::<Vec<_>>(), set.iter_prefix(prefix).collect::<Vec<_>>());
+	}
+	for (i, key) in set.iter().enumerate() {
+		assert!(dawg.contains(&key));
+		assert_eq!(dawg.index_of(&key), Some(i));
+		assert_eq!(dawg.key_at(i), Some(key));
+	}
+	assert_eq!(dawg.key_at(dawg.len()), None);
+	for _ in 0..500 {
+		let key = random_key(&mut rnd, &['a', 'b', 'c', 'd', 'g', 'n', 'i', '\u{e9}'], 10);
+		assert_eq!(dawg.contains(&key), set.contains(&key));
+		assert_eq!(dawg.index_of(&key).is_some(), set.contains(&key));
+	}
+	
+	let empty = crate::StringTreeSet::new().minimize();
+	assert!(empty.is_empty());
+	assert!(!empty.contains(""));
+	assert_eq!(empty.iter().count(), 0);
+}
+
+
+
+#[cfg(feature = "fst")]
+#[test]
+fn string_tree_fst() {
+	use fst::{IntoStreamer, Streamer, automaton::{Automaton, Str}};
+	let mut rnd = rand::rng();
+	let mut string_tree = crate::StringTree::new();
+	for _ in 0..5000 {
+		let key = random_key(&mut rnd, &['a', 'b', 'c', 'd', '\u{e9}', '\u{1f600}'], 10);
+		string_tree.insert(key, rnd.random_range(0..1000u64));
+	}
+	let map = string_tree.to_fst();
+	assert_eq!(map.len(), string_tree.iter().count());
+	assert!(map.as_fst().size() < string_tree.memory_usage().total());
+	for _ in 0..500 {
+		let key = random_key(&mut rnd, &['a', 'b', 'c', 'd', 'e', '\u{e9}', '\u{1f600}'], 12);
+		assert_eq!(map.get(&key), string_tree.get(&key).copied());
+	}
+	
+	let mut prefix_stream = map.search(Str::new("ab").starts_with()).into_stream();
+	let mut prefix_pairs = vec!();
+	while let Some((key, value)) = prefix_stream.next() {
+		prefix_pairs.push((String::from_utf8(key.to_vec()).unwrap(), value));
+	}
+	assert_eq!(prefix_pairs, string_tree.iter_prefix("ab").map(|(key, value)| (key, *value)).collect::<Vec<_>>());
+	let mut range_stream = map.range().ge("b").lt("c").into_stream();
+	let mut range_count = 0;
+	while let Some((key, value)) = range_stream.next() {
+		assert_eq!(string_tree.get(str::from_utf8(key).unwrap()), Some(&value));
+		range_count += 1;
+	}
+	assert_eq!(range_count, string_tree.iter_prefix("b").count());
+	
+	// round trip through the serialized bytes
+	let loaded = fst::Map::new(map.into_fst().into_inner()).unwrap();
+	let from_fst = crate::StringTree::from_fst(&loaded).unwrap();
+	assert_eq!(from_fst.validate(), Ok(()));
+	assert_eq!(from_fst.iter().collect::<Vec<_>>(), string_tree.iter().collect::<Vec<_>>());
+	let non_utf8 = fst::Map::from_iter([(vec!(0xff), 1)]).unwrap();
+	assert!(crate::StringTree::from_fst(&non_utf8).is_err());
+}
+
+
+
+#[derive(Debug, PartialEq)]
+enum Keyword {
+	If,
+	Else,
+	Elif,
+	For,
+	Fn,
+	Cafe,
+}
+
+static KEYWORDS: crate::StaticStringTree<Keyword> = crate::string_tree!{
+	"if" => Keyword::If,
+	"else" => Keyword::Else,
+	"elif" => Keyword::Elif,
+	"for" => Keyword::For,
+	"fn" => Keyword::Fn,
+	"caf\u{e9}" => Keyword::Cafe,
+};
+
+const NUMBERS: crate::StaticStringTree<u32> = crate::string_tree!{
+	"one" => 1,
+	"two" => 2,
+	"three" => 3,
+	"\u{1f600}" => 4,
+	"\u{1f601}" => 5,
+};
+
+#[test]
+fn static_string_tree() {
+	assert_eq!(KEYWORDS.len(), 6);
+	assert_eq!(KEYWORDS.get("else"), Some(&Keyword::Else));
+	assert_eq!(KEYWORDS.get("elif"), Some(&Keyword::Elif));
+	assert_eq!(KEYWORDS.get("el"), None);
+	assert_eq!(KEYWORDS.get("elsewhere"), None);
+	assert!(KEYWORDS.contains_key("caf\u{e9}"));
+	let f = KEYWORDS.step("f").unwrap();
+	assert_eq!(f.value(), None);
+	assert_eq!(f.path(), "f");
+	assert_eq!(f.children().map(|child| child.path()).collect::<Vec<_>>(), vec!("fn", "fo"));
+	assert_eq!(f.get("or"), Some(&Keyword::For));
+	assert_eq!(KEYWORDS.step("caf").unwrap().children().map(|child| child.path()).collect::<Vec<_>>(), vec!("caf\u{e9}"));
+	assert_eq!(KEYWORDS.iter().map(|(key, _)| key).collect::<Vec<_>>(), vec!("caf\u{e9}", "elif", "else", "fn", "for", "if"));
+	assert_eq!(KEYWORDS.iter_prefix("el").map(|(_, value)| value).collect::<Vec<_>>(), vec!(&Keyword::Elif, &Keyword::Else));
+	
+	// compare with a StringTree that has the same keys
+	let string_tree = crate::StringTree::from(NUMBERS.iter().map(|(key, value)| (key, *value)));
+	assert_eq!(NUMBERS.iter().map(|(key, value)| (key, *value)).collect::<Vec<_>>(), string_tree.iter().map(|(key, value)| (key, *value)).collect::<Vec<_>>());
+	let root_children = NUMBERS.root_node().children().map(|child| child.path()).collect::<Vec<_>>();
+	assert_eq!(root_children, string_tree.root_node().children().map(|child| child.path()).collect::<Vec<_>>());
+	assert_eq!(root_children, vec!("o", "t", "\u{1f600}", "\u{1f601}"));
+	assert_eq!(NUMBERS.get("\u{1f601}"), Some(&5));
+	
+	const EMPTY: crate::StaticStringTree<u32> = crate::string_tree!{};
+	assert!(EMPTY.is_empty());
+	assert_eq!(EMPTY.get(""), None);
+	assert_eq!(EMPTY.iter().count(), 0);
+}
+
+
+
+#[test]
+fn fuzzing() {
+	// with `debug-invariants`, every edit already validates the whole tree
+	#[cfg(feature = "debug-invariants")]
+	const ITERATIONS: usize = 2000;
+	#[cfg(all(not(feature = "debug-invariants"), debug_assertions))]
+	const ITERATIONS: usize = 100000;
+	#[cfg(all(not(feature = "debug-invariants"), not(debug_assertions)))]
+	const ITERATIONS: usize = 1000000;
 	
+	fuzz_string_tree(ITERATIONS, 1000, 1000, |rnd| rnd.sample_iter(&Alphanumeric).take(16).map(char::from).collect());
 }
@@ -0,0 +1,227 @@
+use crate::*;
+use std::fmt;
+
+
+
+/// A broken invariant found by `StringTree::validate()` (or `SequenceTree::validate()`, where `S` is the symbol type instead of bytes)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IntegrityError<S = u8> {
+	/// The node vecs don't all have the same length (or there are fewer generations than nodes)
+	LengthMismatch {
+		/// The number of children lists, which is the number of nodes that the other lengths are compared to
+		pointers: usize,
+		/// The number of fill counts
+		fill_counts: usize,
+		/// The number of stubs
+		stubs: usize,
+		/// The number of (parent index, index within parent) entries
+		parents: usize,
+		/// The number of value slots
+		values: usize,
+		/// The number of slot generations, which can be more than the number of nodes
+		generations: usize,
+		/// The number of node versions
+		versions: usize,
+	},
+	/// A node points to a child that is out of bounds
+	ChildOutOfBounds {
+		/// The node with the broken child pointer
+		node: u32,
+		/// The byte that leads to the child
+		byte: S,
+		/// The child index, which is either past the last node or the root node
+		child: u32,
+	},
+	/// A node has more than one child for the same byte
+	DuplicateChildByte {
+		/// The node with the duplicate children
+		node: u32,
+		/// The byte that more than one child is reached with
+		byte: S,
+	},
+	/// A node points to a child whose parent data doesn't point back to it
+	ParentMismatch {
+		/// The node that points to the child
+		node: u32,
+		/// The byte that `node` reaches the child with
+		byte: S,
+		/// The child index
+		child: u32,
+		/// The (parent index, index within parent) that the child has
+		child_parent: (u32, S),
+	},
+	/// A node is reachable through more than one path
+	MultipleParents(u32),
+	/// A node's fill count doesn't match its number of children
+	FillCountMismatch {
+		/// The node with the wrong fill count
+		node: u32,
+		/// The fill count that the node has
+		fill_count: u8,
+		/// The number of children that the node actually has
+		children: usize,
+	},
+	/// A node (that isn't a free slot) can't be reached from the root node
+	UnreachableNode(u32),
+	/// A free slot can be reached from the root node, is listed more than once, or is out of bounds
+	FreeNodeInUse(u32),
+	/// A node other than the root node has no value and no children
+	ValuelessLeaf(u32),
+	/// A node with a value has a path that isn't valid UTF-8
+	InvalidUtf8Path(u32),
+}
+
+impl<S: fmt::Debug> fmt::Display for IntegrityError<S> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::LengthMismatch {pointers, fill_counts, stubs, parents, values, generations, versions} => write!(f, "node vec lengths don't match: pointers {pointers}, fill counts {fill_counts}, stubs {stubs}, parents {parents}, values {values}, generations {generations}, versions {versions}"),
+			Self::ChildOutOfBounds {node, byte, child} => write!(f, "node {node} points to out of bounds child {child} with byte {byte:?}"),
+			Self::DuplicateChildByte {node, byte} => write!(f, "node {node} has multiple children for byte {byte:?}"),
+			Self::ParentMismatch {node, byte, child, child_parent} => write!(f, "node {node} points to child {child} with byte {byte:?}, but the child's parent data is {child_parent:?}"),
+			Self::MultipleParents(node) => write!(f, "node {node} is reachable through more than one path"),
+			Self::FillCountMismatch {node, fill_count, children} => write!(f, "node {node} has a fill count of {fill_count} but has {children} children"),
+			Self::UnreachableNode(node) => write!(f, "node {node} can't be reached from the root node"),
+			Self::FreeNodeInUse(node) => write!(f, "free slot {node} is reachable, listed more than once, or out of bounds"),
+			Self::ValuelessLeaf(node) => write!(f, "node {node} has no value and no children"),
+			Self::InvalidUtf8Path(node) => write!(f, "node {node} has a value but its path isn't valid UTF-8"),
+		}
+	}
+}
+
+impl<S: fmt::Debug> std::error::Error for IntegrityError<S> {}
+
+impl<S: Copy + Ord + Default, T> SequenceTree<S, T> {
+	
+	/// Checks every internal invariant of the tree and returns every violation that was found, where `is_valid_path` decides whether a key is allowed
+	pub(crate) fn integrity_errors(&self, is_valid_path: impl Fn(&[S]) -> bool) -> Vec<IntegrityError<S>> {
+		let mut errors = vec!();
+		let len = self.node_pointers.len();
+		if self.node_fill_counts.len() != len || self.node_stubs.len() != len || self.node_parents.len() != len || self.node_values.len() != len || self.node_generations.len() < len || self.node_versions.len() != len {
+			// the other checks could go out of bounds, so they are skipped
+			return vec!(IntegrityError::LengthMismatch {
+				pointers: len,
+				fill_counts: self.node_fill_counts.len(),
+				stubs: self.node_stubs.len(),
+				parents: self.node_parents.len(),
+				values: self.node_values.len(),
+				generations: self.node_generations.len(),
+				versions: self.node_versions.len(),
+			});
+		}
+		
+		// check every node that can be reached from the root node
+		let mut is_reached = vec!(false; len);
+		is_reached[0] = true;
+		let mut path = vec!();
+		let mut stack = vec!((0, 0usize));
+		while let Some((index, depth)) = stack.pop() {
+			// `depth` is the length of this node's path, so the path of its parent is kept and the last symbol is replaced
+			path.truncate(depth.saturating_sub(1));
+			if index != 0 {
+				path.push(self.node_parents[index as usize].1);
+			}
+			let pointers = &self.node_pointers[index as usize];
+			if self.node_fill_counts[index as usize] != pointers.len() as u8 {
+				errors.push(IntegrityError::FillCountMismatch {node: index, fill_count: self.node_fill_counts[index as usize], children: pointers.len()});
+			}
+			if index != 0 && pointers.is_empty() && self.node_values.is_none(index as usize) {
+				errors.push(IntegrityError::ValuelessLeaf(index));
+			}
+			if self.node_values.is_some(index as usize) && !is_valid_path(&path) {
+				errors.push(IntegrityError::InvalidUtf8Path(index));
+			}
+			for (i, &(byte, child)) in pointers.iter().enumerate() {
+				if pointers[..i].iter().any(|(prev_byte, _)| *prev_byte == byte) {
+					errors.push(IntegrityError::DuplicateChildByte {node: index, byte});
+				}
+				if child as usize >= len || child == 0 {
+					errors.push(IntegrityError::ChildOutOfBounds {node: index, byte, child});
+					continue;
+				}
+				if self.node_parents[child as usize] != (index, byte) {
+					errors.push(IntegrityError::ParentMismatch {node: index, byte, child, child_parent: self.node_parents[child as usize]});
+				}
+				if is_reached[child as usize] {
+					errors.push(IntegrityError::MultipleParents(child));
+					continue;
+				}
+				is_reached[child as usize] = true;
+				stack.push((child, depth + 1));
+			}
+		}
+		
+		// every node must either be reachable or be a free slot, but not both
+		let mut is_free = vec!(false; len);
+		for &index in &self.free_nodes {
+			if index as usize >= len {
+				errors.push(IntegrityError::FreeNodeInUse(index));
+				continue;
+			}
+			if is_reached[index as usize] || is_free[index as usize] {
+				errors.push(IntegrityError::FreeNodeInUse(index));
+			}
+			is_free[index as usize] = true;
+		}
+		for index in 0..len {
+			if !is_reached[index] && !is_free[index] {
+				errors.push(IntegrityError::UnreachableNode(index as u32));
+			}
+		}
+		
+		errors
+	}
+	
+}
+
+impl<T> StringTree<T> {
+	
+	/// Checks every internal invariant of the tree, and returns every violation that was found
+	/// 
+	/// This is mainly meant for testing and debugging, and it takes O(n) time and memory. With the `debug-invariants` feature, this is run (and panics on errors) after every edit.
+	pub fn validate(&self) -> Result<(), Vec<IntegrityError>> {
+		let errors = self.integrity_errors(|path| str::from_utf8(path).is_ok());
+		if errors.is_empty() {Ok(())} else {Err(errors)}
+	}
+	
+	/// Panics if `validate()` finds any errors, but only when the `debug-invariants` feature is enabled
+	#[inline]
+	pub(crate) fn check_invariants(&self) {
+		#[cfg(feature = "debug-invariants")]
+		if let Err(errors) = self.validate() {
+			panic!("StringTree invariants are broken: {errors:?}");
+		}
+	}
+	/// Same as `check_invariants()`, but allows any path, for the tree inside a ByteStringTree (where any bytes are allowed)
+	#[inline]
+	pub(crate) fn check_invariants_bytes(&self) {
+		#[cfg(feature = "debug-invariants")]
+		{
+			let errors = self.integrity_errors(|_| true);
+			if !errors.is_empty() {
+				panic!("ByteStringTree invariants are broken: {errors:?}");
+			}
+		}
+	}
+	
+}
+
+impl<S: Symbol, T> SequenceTree<S, T> {
+	
+	/// Checks every internal invariant of the tree, and returns every violation that was found
+	/// 
+	/// This is the same as `StringTree::validate()`, except that any key is allowed
+	pub fn validate(&self) -> Result<(), Vec<IntegrityError<S>>> {
+		let errors = self.integrity_errors(|_| true);
+		if errors.is_empty() {Ok(())} else {Err(errors)}
+	}
+	
+	/// Panics if `validate()` finds any errors, but only when the `debug-invariants` feature is enabled
+	#[inline]
+	pub(crate) fn check_invariants(&self) {
+		#[cfg(feature = "debug-invariants")]
+		if let Err(errors) = self.validate() {
+			panic!("SequenceTree invariants are broken: {errors:?}");
+		}
+	}
+	
+}